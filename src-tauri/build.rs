@@ -36,9 +36,15 @@ fn main() {
         "../cline/proto/cline/web.proto",
     ];
     
+    // 同时输出描述符集合，供没有专门处理器的方法通过反射动态转发
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    // 生成的类型实现 prost::Name，按类型名查找描述符做 proto3 JSON 转换
+    let mut prost_config = prost_build::Config::new();
+    prost_config.enable_type_names();
     tonic_build::configure()
         .build_server(false)
         .build_client(true) // Cline 我们需要客户端
-        .compile(&cline_proto_files, &proto_include_dirs)
+        .file_descriptor_set_path(out_dir.join("cline_descriptor.bin"))
+        .compile_with_config(prost_config, &cline_proto_files, &proto_include_dirs)
         .unwrap_or_else(|e| panic!("Failed to compile cline protos: {}", e));
 }
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde_json::Value;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, EventTarget};
+
+use crate::grpc_client::{GrpcError, StreamCancel, StreamConfig, StreamEvent};
+
+#[cfg(test)]
+mod tests;
+
+// 没有提供 Channel 时，响应以该事件发送给发起请求的 webview
+pub const WEBVIEW_MESSAGE_EVENT: &str = "grpc-message";
+
+// 每个流最多缓存的消息数，超过后暂停读取 cline-core 的流，直到 webview 跟上
+pub const STREAM_QUEUE_CAPACITY: usize = 64;
+
+// 发送给 webview 的消息
+pub type WebviewSink = Arc<dyn Fn(Value) + Send + Sync>;
+
+// 构造 grpc_response 消息，出错时 error_code 为 unavailable、timeout、not-implemented 等错误码
+// 流式请求的每条消息带 is_streaming: true 和递增的 sequence_number，流结束时发送 is_streaming: false
+pub fn grpc_response(
+    request_id: &str,
+    message: Option<Value>,
+    error: Option<&GrpcError>,
+    is_streaming: bool,
+    sequence_number: Option<u64>,
+) -> Value {
+    let mut response = serde_json::json!({
+        "request_id": request_id,
+        "message": message,
+        "error": error.map(|e| e.message.clone()),
+        "error_code": error.map(|e| e.code),
+        "is_streaming": is_streaming
+    });
+    if let Some(sequence_number) = sequence_number {
+        response["sequence_number"] = serde_json::json!(sequence_number);
+    }
+
+    serde_json::json!({
+        "type": "grpc_response",
+        "grpc_response": response
+    })
+}
+
+// 发送到发起请求的 webview：优先使用请求附带的 Channel，否则发送定向事件
+pub fn webview_sink(app_handle: AppHandle, webview: &str, channel: Option<Channel<Value>>) -> WebviewSink {
+    match channel {
+        Some(channel) => Arc::new(move |message| {
+            if let Err(e) = channel.send(message) {
+                println!("[DEBUG] ❌ Failed to send response to frontend channel: {}", e);
+            }
+        }),
+        None => {
+            let target = EventTarget::WebviewWindow { label: webview.to_string() };
+            Arc::new(move |message| {
+                if let Err(e) = app_handle.emit_to(target.clone(), WEBVIEW_MESSAGE_EVENT, message) {
+                    println!("[DEBUG] ❌ Failed to send response to frontend: {}", e);
+                }
+            })
+        }
+    }
+}
+
+struct RegisteredStream {
+    // 发起请求的 webview，页面重新加载或关闭时取消其全部订阅
+    webview: String,
+    cancel: StreamCancel,
+}
+
+// 正在进行的流式请求，按 request_id 索引
+#[derive(Clone, Default)]
+pub struct StreamRegistry {
+    streams: Arc<Mutex<HashMap<String, RegisteredStream>>>,
+}
+
+impl StreamRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 登记新的流式请求；request_id 重复时取消之前的请求
+    pub fn register(&self, request_id: &str, webview: &str) -> StreamCancel {
+        let cancel = StreamCancel::new();
+        let previous = self.streams.lock().unwrap().insert(
+            request_id.to_string(),
+            RegisteredStream {
+                webview: webview.to_string(),
+                cancel: cancel.clone(),
+            },
+        );
+        if let Some(previous) = previous {
+            previous.cancel.cancel();
+        }
+        cancel
+    }
+
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.streams.lock().unwrap().remove(request_id) {
+            Some(stream) => {
+                stream.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 取消某个 webview 的全部订阅，返回取消的数量
+    pub fn cancel_webview(&self, webview: &str) -> usize {
+        let mut streams = self.streams.lock().unwrap();
+        let request_ids: Vec<String> = streams
+            .iter()
+            .filter(|(_, stream)| stream.webview == webview)
+            .map(|(request_id, _)| request_id.clone())
+            .collect();
+        for request_id in &request_ids {
+            if let Some(stream) = streams.remove(request_id) {
+                stream.cancel.cancel();
+            }
+        }
+        request_ids.len()
+    }
+
+    // 流结束后移除登记；request_id 已被新的请求复用时保留新的登记
+    pub fn remove(&self, request_id: &str, cancel: &StreamCancel) {
+        let mut streams = self.streams.lock().unwrap();
+        if streams.get(request_id).map_or(false, |stream| stream.cancel.same(cancel)) {
+            streams.remove(request_id);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.lock().unwrap().len()
+    }
+}
+
+// 将流式请求的每条消息转发到 webview，并登记到 registry 以便取消
+pub fn registered_stream_config(registry: &StreamRegistry, request_id: String, webview: &str, sink: WebviewSink) -> StreamConfig {
+    let cancel = registry.register(&request_id, webview);
+
+    // 发送结束标记时移除登记
+    let registered_registry = registry.clone();
+    let registered = cancel.clone();
+    let registered_request_id = request_id.clone();
+    let sink: WebviewSink = Arc::new(move |message| {
+        let is_end = message["grpc_response"]["is_streaming"] == false;
+        sink(message);
+        if is_end {
+            registered_registry.remove(&registered_request_id, &registered);
+        }
+    });
+
+    let mut config = stream_config_with_sink(request_id, sink);
+    config.cancel = Some(cancel);
+    config
+}
+
+// 以流式方式请求一元方法时，处理器直接返回响应而不会接管流
+// 此时把这条响应作为唯一的流消息发送，再发送结束标记以移除登记；返回是否由此结束了流
+pub async fn finish_unstarted_stream(config: &StreamConfig, response: &Value) -> bool {
+    if config.is_started() {
+        return false;
+    }
+    config.send(response.clone()).await;
+    config.complete(None).await;
+    true
+}
+
+// 流式消息先写入有界队列，再由后台任务按顺序编号并发送
+// 队列写满时上游的 send 会等待，cline-core 的流随之被 HTTP/2 流控暂停
+pub fn stream_config_with_sink(request_id: String, sink: WebviewSink) -> StreamConfig {
+    let (queue, mut receiver) = tokio::sync::mpsc::channel(STREAM_QUEUE_CAPACITY);
+
+    tauri::async_runtime::spawn(async move {
+        let mut sequence_number = 0;
+        while let Some(event) = receiver.recv().await {
+            match event {
+                StreamEvent::Message(message) => {
+                    sink(grpc_response(&request_id, Some(message), None, true, Some(sequence_number)));
+                    sequence_number += 1;
+                }
+                StreamEvent::End(error) => {
+                    println!("[DEBUG] Stream {} ended after {} messages", request_id, sequence_number);
+                    sink(grpc_response(&request_id, None, error.as_ref(), false, Some(sequence_number)));
+                    break;
+                }
+            }
+        }
+    });
+
+    StreamConfig::queued(queue)
+}
@@ -0,0 +1,209 @@
+#[cfg(test)]
+mod bridge_tests {
+    use crate::bridge::{finish_unstarted_stream, grpc_response, registered_stream_config, stream_config_with_sink, StreamRegistry, WebviewSink, STREAM_QUEUE_CAPACITY};
+    use crate::grpc_client::utils::spawn_stream_task;
+    use crate::grpc_client::GrpcError;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn recording_sink() -> (WebviewSink, Arc<Mutex<Vec<Value>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let recorder = sent.clone();
+        let sink: WebviewSink = Arc::new(move |message| recorder.lock().unwrap().push(message));
+        (sink, sent)
+    }
+
+    // 消息由后台任务投递，等待投递完成
+    async fn wait_for(sent: &Arc<Mutex<Vec<Value>>>, count: usize) -> Vec<Value> {
+        for _ in 0..100 {
+            if sent.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        sent.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_unary_response_has_no_sequence_number() {
+        let response = grpc_response("req-1", Some(json!({"value": 1})), None, false, None);
+
+        assert_eq!(response["type"], "grpc_response");
+        assert_eq!(response["grpc_response"]["request_id"], "req-1");
+        assert_eq!(response["grpc_response"]["message"]["value"], 1);
+        assert_eq!(response["grpc_response"]["error"], Value::Null);
+        assert_eq!(response["grpc_response"]["error_code"], Value::Null);
+        assert_eq!(response["grpc_response"]["is_streaming"], false);
+        assert!(response["grpc_response"].get("sequence_number").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_messages_are_tagged_and_terminated() {
+        let (sink, sent) = recording_sink();
+        let config = stream_config_with_sink("req-2".to_string(), sink);
+        assert!(config.enable_streaming);
+
+        config.send(json!({"stateJson": "{}"})).await;
+        config.send(json!({"stateJson": "{\"mode\":\"act\"}"})).await;
+        config.complete(None).await;
+
+        let sent = wait_for(&sent, 3).await;
+        assert_eq!(sent.len(), 3);
+        for (index, message) in sent.iter().enumerate() {
+            assert_eq!(message["grpc_response"]["request_id"], "req-2");
+            assert_eq!(message["grpc_response"]["sequence_number"], index as u64);
+        }
+        assert_eq!(sent[0]["grpc_response"]["is_streaming"], true);
+        assert_eq!(sent[1]["grpc_response"]["message"]["stateJson"], "{\"mode\":\"act\"}");
+
+        // 结束标记不带消息
+        assert_eq!(sent[2]["grpc_response"]["is_streaming"], false);
+        assert_eq!(sent[2]["grpc_response"]["message"], Value::Null);
+        assert_eq!(sent[2]["grpc_response"]["error"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_reported_in_end_marker() {
+        let (sink, sent) = recording_sink();
+        let config = stream_config_with_sink("req-3".to_string(), sink);
+
+        config.complete(Some(GrpcError::unavailable("stream reset"))).await;
+
+        let sent = wait_for(&sent, 1).await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["grpc_response"]["is_streaming"], false);
+        assert_eq!(sent[0]["grpc_response"]["error"], "stream reset");
+        assert_eq!(sent[0]["grpc_response"]["error_code"], "unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_applies_backpressure() {
+        // webview 暂时无法接收消息
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let blocked = Mutex::new(blocked);
+        let delivered = Arc::new(Mutex::new(0usize));
+        let counter = delivered.clone();
+        let sink: WebviewSink = Arc::new(move |_message| {
+            let _ = blocked.lock().unwrap().recv();
+            *counter.lock().unwrap() += 1;
+        });
+        let config = stream_config_with_sink("req-4".to_string(), sink);
+
+        // 一条消息正在投递，队列中最多再缓存 STREAM_QUEUE_CAPACITY 条
+        let producer = config.clone();
+        let sending = tokio::spawn(async move {
+            for index in 0..STREAM_QUEUE_CAPACITY + 2 {
+                producer.send(json!({ "index": index })).await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!sending.is_finished());
+
+        for _ in 0..STREAM_QUEUE_CAPACITY + 2 {
+            release.send(()).unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(5), sending).await.unwrap().unwrap();
+        for _ in 0..100 {
+            if *delivered.lock().unwrap() == STREAM_QUEUE_CAPACITY + 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(*delivered.lock().unwrap(), STREAM_QUEUE_CAPACITY + 2);
+    }
+
+    #[test]
+    fn test_registry_cancels_by_request_and_webview() {
+        let registry = StreamRegistry::new();
+        let first = registry.register("req-a", "main");
+        let second = registry.register("req-b", "main");
+        let other = registry.register("req-c", "settings");
+
+        assert!(registry.cancel("req-a"));
+        assert!(first.is_cancelled());
+        assert!(!registry.cancel("req-a"));
+
+        assert_eq!(registry.cancel_webview("main"), 1);
+        assert!(second.is_cancelled());
+        assert!(!other.is_cancelled());
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_reused_request_id_cancels_previous_stream() {
+        let registry = StreamRegistry::new();
+        let previous = registry.register("req-d", "main");
+        let current = registry.register("req-d", "main");
+
+        assert!(previous.is_cancelled());
+        // 旧流结束时不能移除新的登记
+        registry.remove("req-d", &previous);
+        assert_eq!(registry.len(), 1);
+        registry.remove("req-d", &current);
+        assert_eq!(registry.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_background_stream() {
+        let registry = StreamRegistry::new();
+        let (sink, sent) = recording_sink();
+        let config = registered_stream_config(&registry, "req-e".to_string(), "main", sink);
+
+        // 模拟永远不结束的订阅
+        let stream_config = config.clone();
+        spawn_stream_task(config, async move {
+            stream_config.send(json!({"text": "partial"})).await;
+            futures::future::pending::<()>().await;
+            Ok(())
+        });
+        wait_for(&sent, 1).await;
+        assert!(registry.cancel("req-e"));
+
+        let sent = wait_for(&sent, 2).await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0]["grpc_response"]["is_streaming"], true);
+        assert_eq!(sent[1]["grpc_response"]["is_streaming"], false);
+        assert_eq!(registry.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unary_response_to_stream_request_is_finished() {
+        let registry = StreamRegistry::new();
+        let (sink, sent) = recording_sink();
+        let config = registered_stream_config(&registry, "req-f".to_string(), "main", sink);
+
+        // 处理器没有接管流，响应作为唯一的流消息发送并结束流
+        assert!(finish_unstarted_stream(&config, &json!({"value": "done"})).await);
+
+        let sent = wait_for(&sent, 2).await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0]["grpc_response"]["is_streaming"], true);
+        assert_eq!(sent[0]["grpc_response"]["message"]["value"], "done");
+        assert_eq!(sent[1]["grpc_response"]["is_streaming"], false);
+
+        // 结束标记发送后移除登记
+        for _ in 0..100 {
+            if registry.len() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(registry.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_started_stream_is_left_to_its_task() {
+        let registry = StreamRegistry::new();
+        let (sink, sent) = recording_sink();
+        let config = registered_stream_config(&registry, "req-g".to_string(), "main", sink);
+
+        spawn_stream_task(config.clone(), futures::future::pending());
+        assert!(!finish_unstarted_stream(&config, &json!({})).await);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(sent.lock().unwrap().is_empty());
+        assert_eq!(registry.len(), 1);
+        registry.cancel("req-g");
+    }
+}
@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde_json::Value;
+
+use crate::grpc_client::{
+    error::GrpcError,
+    types::GrpcResult,
+    utils::log_debug,
+};
+
+// Chrome 远程调试的默认地址
+pub const DEFAULT_DEBUG_ENDPOINTS: [&str; 2] = ["http://localhost:9222", "http://127.0.0.1:9222"];
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+// PATH 中查找的可执行文件名，按优先级排列
+const CHROME_BINARIES: [&str; 6] = [
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "chrome",
+    "brave-browser",
+];
+
+// 不在 PATH 中时的常见安装位置，包括 snap 和 flatpak 的导出目录
+const CHROME_INSTALL_PATHS: [&str; 7] = [
+    "/opt/google/chrome/chrome",
+    "/opt/google/chrome/google-chrome",
+    "/usr/lib/chromium/chromium",
+    "/usr/lib/chromium-browser/chromium-browser",
+    "/snap/bin/chromium",
+    "/var/lib/flatpak/exports/bin/com.google.Chrome",
+    "/var/lib/flatpak/exports/bin/org.chromium.Chromium",
+];
+
+// 用户级 flatpak 安装，相对于 HOME
+const CHROME_USER_INSTALL_PATHS: [&str; 2] = [
+    ".local/share/flatpak/exports/bin/com.google.Chrome",
+    ".local/share/flatpak/exports/bin/org.chromium.Chromium",
+];
+
+// 查找本机安装的 Chrome / Chromium
+pub fn find_chrome() -> Option<PathBuf> {
+    let path_var = std::env::var("PATH").ok();
+    let home = std::env::var("HOME").ok().map(PathBuf::from);
+    find_chrome_with(path_var.as_deref(), home.as_deref(), |path| path.is_file())
+}
+
+// 依次检查 PATH、常见安装目录和用户级 flatpak，exists 便于在测试中替换文件系统
+pub fn find_chrome_with<F: Fn(&Path) -> bool>(path_var: Option<&str>, home: Option<&Path>, exists: F) -> Option<PathBuf> {
+    let path_dirs: Vec<PathBuf> = path_var
+        .map(|path_var| std::env::split_paths(path_var).collect())
+        .unwrap_or_default();
+
+    let in_path = CHROME_BINARIES
+        .iter()
+        .flat_map(|binary| path_dirs.iter().map(move |dir| dir.join(binary)));
+    let installed = CHROME_INSTALL_PATHS.iter().map(PathBuf::from);
+    let user_installed = home
+        .into_iter()
+        .flat_map(|home| CHROME_USER_INSTALL_PATHS.iter().map(move |path| home.join(path)));
+
+    in_path.chain(installed).chain(user_installed).find(|candidate| exists(candidate))
+}
+
+// 通过 DevTools HTTP 接口探测远程调试端口
+#[derive(Debug, Clone)]
+pub struct ChromeDiscovery {
+    http: reqwest::Client,
+}
+
+impl Default for ChromeDiscovery {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROBE_TIMEOUT)
+    }
+}
+
+impl ChromeDiscovery {
+    pub fn new(timeout: Duration) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .connect_timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        Self { http }
+    }
+
+    // 返回 /json/version 的内容，其中包含 Browser 和 webSocketDebuggerUrl
+    pub async fn probe(&self, endpoint: &str) -> GrpcResult<Value> {
+        let url = format!("{}/json/version", endpoint.trim_end_matches('/'));
+        log_debug(&format!("Probing Chrome DevTools endpoint {}", url));
+
+        let response = self.http.get(&url).send().await.map_err(|e| {
+            if e.is_timeout() {
+                GrpcError::timeout(format!("{} timeout", url))
+            } else {
+                GrpcError::unavailable(format!("{} is not reachable: {}", url, e))
+            }
+        })?;
+        if !response.status().is_success() {
+            return Err(GrpcError::unavailable(format!("{} returned {}", url, response.status())).into());
+        }
+        let version: Value = response
+            .json()
+            .await
+            .map_err(|e| GrpcError::internal(format!("Invalid response from {}: {}", url, e)))?;
+        if version["webSocketDebuggerUrl"].as_str().is_none() {
+            return Err(GrpcError::unavailable(format!("{} is not a Chrome DevTools endpoint", endpoint)).into());
+        }
+        Ok(version)
+    }
+
+    // 按 BrowserConnection 消息的格式返回连接测试结果
+    pub async fn test_connection(&self, endpoint: &str) -> Value {
+        match self.probe(endpoint).await {
+            Ok(version) => serde_json::json!({
+                "success": true,
+                "message": format!("Connected to {}", version["Browser"].as_str().unwrap_or("Chrome")),
+                "endpoint": version["webSocketDebuggerUrl"],
+            }),
+            Err(e) => serde_json::json!({
+                "success": false,
+                "message": format!("Failed to connect to {}: {}", endpoint, e),
+            }),
+        }
+    }
+
+    // 在默认调试端口上查找正在运行的浏览器
+    pub async fn discover(&self) -> Option<Value> {
+        for endpoint in DEFAULT_DEBUG_ENDPOINTS {
+            let result = self.test_connection(endpoint).await;
+            if result["success"] == true {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
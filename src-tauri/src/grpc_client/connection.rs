@@ -1,14 +1,22 @@
 use std::collections::HashMap;
-use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use tonic::transport::Channel;
 use serde_json::Value;
 
 use crate::grpc_client::{
-    types::{GrpcResult, ServiceType, ServiceHandler, LruCache, CacheConfig},
+    error::{GrpcError, UnhandledMethod},
+    types::{GrpcResult, ServiceType, ServiceHandler, LruCache, CacheConfig, StreamConfig},
     utils::{log_debug, log_success, log_error, DEFAULT_CONNECT_TIMEOUT, RetryConfig, PerformanceStats},
-    services::{StateServiceHandler, UiServiceHandler, McpServiceHandler, AccountServiceHandler, ModelsServiceHandler},
+    local_models::LocalModelDiscovery,
+    reflection::ReflectionHandler,
+    services::{StateServiceHandler, UiServiceHandler, McpServiceHandler, ModelsServiceHandler, TaskServiceHandler, FileServiceHandler, DesktopFileOps, CheckpointsServiceHandler, BrowserServiceHandler},
 };
 
+// 未指定时使用的 ProtoBus 地址；桌面应用运行时端口由启动流程动态分配
+pub const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:26040";
+
 #[derive(Debug, Clone)]
 pub struct ConnectionConfig {
     pub endpoint: std::string::String,
@@ -18,12 +26,15 @@ pub struct ConnectionConfig {
     pub cache_config: CacheConfig,
     pub enable_performance_monitoring: bool,
     pub max_concurrent_requests: usize,
+    pub local_models: LocalModelDiscovery,
+    // 桌面端的原生文件操作，未设置时 FileService 请求全部转发给 cline-core
+    pub desktop_files: Option<Arc<dyn DesktopFileOps>>,
 }
 
 impl Default for ConnectionConfig {
     fn default() -> Self {
         Self {
-            endpoint: "http://127.0.0.1:26040".to_string(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             retry_config: RetryConfig {
                 max_retries: 8, // 增加重试次数
@@ -35,20 +46,36 @@ impl Default for ConnectionConfig {
             cache_config: CacheConfig::default(),
             enable_performance_monitoring: true,
             max_concurrent_requests: 100,
+            local_models: LocalModelDiscovery::default(),
+            desktop_files: None,
         }
     }
 }
 
-pub struct ClineGrpcClient {
+// 连接状态，只在建立、检查或重建连接时短暂加锁
+struct ConnectionState {
+    // 当前的 ProtoBus 地址，cline-core 换端口重启后会被更新
+    endpoint: String,
     channel: Option<Channel>,
-    config: ConnectionConfig,
     services: HashMap<ServiceType, ServiceHandler>,
-    last_successful_connection: Option<std::time::Instant>,
+    last_successful_connection: Option<Instant>,
     connection_failures: usize,
+    // 最近一次完整重试后仍然失败的时间和错误，等待 connecting 锁的请求据此直接失败
+    last_connect_failure: Option<(Instant, String)>,
+}
+
+// 应用内共享的客户端；克隆后共享同一个连接、缓存和性能统计
+// 每个请求克隆一份服务处理器后独立执行，并发请求之间不需要互相等待
+#[derive(Clone)]
+pub struct ClineGrpcClient {
+    config: ConnectionConfig,
+    state: Arc<RwLock<ConnectionState>>,
+    // 同一时间只有一个请求负责建立连接，其余请求等待后复用该连接
+    connecting: Arc<tokio::sync::Mutex<()>>,
     // 性能监控和缓存
-    performance_stats: PerformanceStats,
-    cache: LruCache,
-    active_requests: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    performance_stats: Arc<Mutex<PerformanceStats>>,
+    cache: Arc<Mutex<LruCache>>,
+    active_requests: Arc<AtomicUsize>,
 }
 
 impl ClineGrpcClient {
@@ -56,32 +83,73 @@ impl ClineGrpcClient {
         Self::with_config(ConnectionConfig::default())
     }
     
+    // 连接到指定的 ProtoBus 地址，其余配置使用默认值
+    pub fn with_endpoint(endpoint: &str) -> Self {
+        Self::with_config(ConnectionConfig {
+            endpoint: endpoint.to_string(),
+            ..ConnectionConfig::default()
+        })
+    }
+    
     pub fn with_config(config: ConnectionConfig) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(ConnectionState {
+                endpoint: config.endpoint.clone(),
+                channel: None,
+                services: Self::create_services(None, &config),
+                last_successful_connection: None,
+                connection_failures: 0,
+                last_connect_failure: None,
+            })),
+            connecting: Arc::new(tokio::sync::Mutex::new(())),
+            cache: Arc::new(Mutex::new(LruCache::new(config.cache_config.clone()))),
+            config,
+            performance_stats: Arc::new(Mutex::new(PerformanceStats::default())),
+            active_requests: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+    
+    // 注册各个服务处理器，有连接时让处理器使用该连接
+    fn create_services(channel: Option<&Channel>, config: &ConnectionConfig) -> HashMap<ServiceType, ServiceHandler> {
         let mut services: HashMap<ServiceType, ServiceHandler> = HashMap::new();
-        
-        // 注册各个服务处理器
         services.insert(ServiceType::State, ServiceHandler::State(StateServiceHandler::new()));
         services.insert(ServiceType::Ui, ServiceHandler::Ui(UiServiceHandler::new()));
         services.insert(ServiceType::Mcp, ServiceHandler::Mcp(McpServiceHandler::new()));
-        services.insert(ServiceType::Account, ServiceHandler::Account(AccountServiceHandler::new()));
-        services.insert(ServiceType::Models, ServiceHandler::Models(ModelsServiceHandler::new()));
+        services.insert(ServiceType::Models, ServiceHandler::Models(ModelsServiceHandler::with_local_models(config.local_models.clone())));
+        services.insert(ServiceType::Task, ServiceHandler::Task(TaskServiceHandler::new()));
+        services.insert(ServiceType::File, ServiceHandler::File(FileServiceHandler::with_desktop(config.desktop_files.clone())));
+        services.insert(ServiceType::Checkpoints, ServiceHandler::Checkpoints(CheckpointsServiceHandler::new()));
+        services.insert(ServiceType::Browser, ServiceHandler::Browser(BrowserServiceHandler::new()));
         
-        Self {
-            channel: None,
-            cache: LruCache::new(config.cache_config.clone()),
-            config,
-            services,
-            last_successful_connection: None,
-            connection_failures: 0,
-            performance_stats: PerformanceStats::default(),
-            active_requests: std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+        if let Some(channel) = channel {
+            for (service_type, service_handler) in &mut services {
+                log_debug(&format!("Initializing {} client", service_type.as_str()));
+                
+                match service_handler {
+                    ServiceHandler::State(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::Ui(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::Mcp(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::Models(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::Task(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::File(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::Checkpoints(handler) => handler.set_client(channel.clone()),
+                    ServiceHandler::Browser(handler) => handler.set_client(channel.clone()),
+                }
+            }
         }
+        services
+    }
+    
+    pub async fn connect(&self) -> GrpcResult<()> {
+        let _connecting = self.connecting.lock().await;
+        self.establish_connection().await
     }
     
-    pub async fn connect(&mut self) -> GrpcResult<()> {
-        log_debug(&format!("Connecting to cline-core gRPC server at {}", self.config.endpoint));
+    // 调用方需要持有 connecting 锁
+    async fn establish_connection(&self) -> GrpcResult<()> {
+        let endpoint = self.endpoint();
+        log_debug(&format!("Connecting to cline-core gRPC server at {}", endpoint));
         
-        let endpoint = self.config.endpoint.clone();
         let connect_timeout = self.config.connect_timeout;
         let retry_config = self.config.retry_config.clone();
         
@@ -121,22 +189,15 @@ impl ClineGrpcClient {
             
             match connection_result {
                 Ok(Ok(channel)) => {
-                    // 连接成功
-                    self.channel = Some(channel.clone());
-                    self.last_successful_connection = Some(std::time::Instant::now());
-                    self.connection_failures = 0;
-                    
-                    // 为所有服务初始化连接
-                    for (service_type, service_handler) in &mut self.services {
-                        log_debug(&format!("Initializing {} client", service_type.as_str()));
-                        
-                        match service_handler {
-                            ServiceHandler::State(handler) => handler.set_client(channel.clone()),
-                            ServiceHandler::Ui(handler) => handler.set_client(channel.clone()),
-                            ServiceHandler::Mcp(handler) => handler.set_client(channel.clone()),
-                            ServiceHandler::Account(handler) => handler.set_client(channel.clone()),
-                            ServiceHandler::Models(handler) => handler.set_client(channel.clone()),
-                        }
+                    // 连接成功，所有服务处理器共用这个连接
+                    let services = Self::create_services(Some(&channel), &self.config);
+                    {
+                        let mut state = self.state.write().unwrap();
+                        state.channel = Some(channel);
+                        state.services = services;
+                        state.last_successful_connection = Some(Instant::now());
+                        state.connection_failures = 0;
+                        state.last_connect_failure = None;
                     }
                     
                     if attempt > 0 {
@@ -159,23 +220,46 @@ impl ClineGrpcClient {
             }
         }
         
-        self.connection_failures += 1;
+        let error = last_error.map_or_else(|| "Connection failed".to_string(), |e| e.to_string());
+        {
+            let mut state = self.state.write().unwrap();
+            state.connection_failures += 1;
+            state.last_connect_failure = Some((Instant::now(), error.clone()));
+        }
         log_error(&format!(
             "Connection failed after {} attempts",
             retry_config.max_retries + 1
         ));
         
-        Err(last_error.unwrap_or_else(|| "Connection failed".into()))
+        Err(error.into())
     }
     
-    pub async fn ensure_connected(&mut self) -> GrpcResult<()> {
-        // 检查连接是否存在
-        if self.channel.is_none() {
-            return self.connect().await;
+    pub async fn ensure_connected(&self) -> GrpcResult<()> {
+        // 已有连接且不需要健康检查时直接返回，不等待 connecting 锁
+        if self.is_connection_fresh() {
+            return Ok(());
+        }
+        
+        let waiting_since = Instant::now();
+        let _connecting = self.connecting.lock().await;
+        
+        // 等待期间其他请求可能已经建立了连接
+        let (connected, last_success, last_failure) = {
+            let state = self.state.read().unwrap();
+            (state.channel.is_some(), state.last_successful_connection, state.last_connect_failure.clone())
+        };
+        if !connected {
+            // 等待期间已有请求完成了一整轮重试仍然失败，直接返回该错误，避免每个请求依次重试
+            if let Some((failed_at, error)) = last_failure {
+                if failed_at >= waiting_since {
+                    return Err(error.into());
+                }
+            }
+            return self.establish_connection().await;
         }
         
         // 检查连接是否需要健康检查
-        if let Some(last_success) = self.last_successful_connection {
+        if let Some(last_success) = last_success {
             let elapsed = last_success.elapsed();
             if elapsed > self.config.health_check_interval {
                 log_debug("Performing connection health check");
@@ -183,12 +267,14 @@ impl ClineGrpcClient {
                 // 进行健康检查，如果失败则重新连接
                 if let Err(e) = self.health_check().await {
                     log_error(&format!("Health check failed: {}", e));
-                    self.connection_failures += 1;
-                    self.channel = None;
-                    return self.connect().await;
+                    {
+                        let mut state = self.state.write().unwrap();
+                        state.connection_failures += 1;
+                        state.channel = None;
+                    }
+                    return self.establish_connection().await;
                 } else {
-                    self.last_successful_connection = Some(std::time::Instant::now());
-                    self.connection_failures = 0;
+                    self.record_success();
                 }
             }
         }
@@ -196,9 +282,17 @@ impl ClineGrpcClient {
         Ok(())
     }
     
+    fn is_connection_fresh(&self) -> bool {
+        let state = self.state.read().unwrap();
+        state.channel.is_some()
+            && state.last_successful_connection
+                .map_or(true, |last_success| last_success.elapsed() <= self.config.health_check_interval)
+    }
+    
     // 健康检查方法
     async fn health_check(&self) -> GrpcResult<()> {
-        if let Some(_channel) = &self.channel {
+        let connected = self.state.read().unwrap().channel.is_some();
+        if connected {
             // 这里可以使用 tonic-health 包来进行正式的健康检查
             // 但为了简化，我们只检查连接是否还在
             
@@ -212,122 +306,220 @@ impl ClineGrpcClient {
         }
     }
     
+    fn record_success(&self) {
+        let mut state = self.state.write().unwrap();
+        state.connection_failures = 0;
+        state.last_successful_connection = Some(Instant::now());
+    }
+    
+    // 返回累计的失败次数
+    fn record_failure(&self) -> usize {
+        let mut state = self.state.write().unwrap();
+        state.connection_failures += 1;
+        state.connection_failures
+    }
+    
+    // 克隆一份服务处理器，请求在克隆上执行，不占用共享状态
+    fn service_handler(&self, service_type: &ServiceType) -> Option<ServiceHandler> {
+        self.state.read().unwrap().services.get(service_type).cloned()
+    }
+    
     pub async fn handle_request(
-        &mut self, 
+        &self, 
         service: &str, 
         method: &str, 
         message: &Value
     ) -> GrpcResult<Value> {
-        let start_time = std::time::Instant::now();
-        let cache_key = format!("{}:{}:{}", service, method, serde_json::to_string(message).unwrap_or_default());
+        self.handle_request_with_config(service, method, message, None).await
+    }
+    
+    // 跳过缓存直接向 cline-core 请求，用于必须读取最新状态的调用，结果仍会更新缓存
+    pub async fn handle_request_uncached(
+        &self, 
+        service: &str, 
+        method: &str, 
+        message: &Value
+    ) -> GrpcResult<Value> {
+        self.execute(service, method, message, None, false).await
+    }
+    
+    // stream_config 不为空时，订阅类方法会在后台把每条流式消息交给回调
+    pub async fn handle_request_with_config(
+        &self, 
+        service: &str, 
+        method: &str, 
+        message: &Value,
+        stream_config: Option<StreamConfig>
+    ) -> GrpcResult<Value> {
+        self.execute(service, method, message, stream_config, true).await
+    }
+    
+    async fn execute(
+        &self, 
+        service: &str, 
+        method: &str, 
+        message: &Value,
+        stream_config: Option<StreamConfig>,
+        use_cache: bool
+    ) -> GrpcResult<Value> {
+        let start_time = Instant::now();
         
         // 检查并发请求限制
-        let active_count = self.active_requests.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let active_count = self.active_requests.fetch_add(1, Ordering::Relaxed);
         if active_count >= self.config.max_concurrent_requests {
-            self.active_requests.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-            return Err("Too many concurrent requests".into());
+            self.active_requests.fetch_sub(1, Ordering::Relaxed);
+            return Err(GrpcError::unavailable("Too many concurrent requests").into());
+        }
+        
+        let result = self.dispatch(service, method, message, stream_config, use_cache).await;
+        
+        // 记录性能统计
+        let duration = start_time.elapsed();
+        let should_cleanup = {
+            let mut performance_stats = self.performance_stats.lock().unwrap();
+            if self.config.enable_performance_monitoring {
+                performance_stats.record_request(duration, result.is_ok());
+            }
+            performance_stats.should_cleanup()
+        };
+        
+        // 减少活跃请求计数
+        self.active_requests.fetch_sub(1, Ordering::Relaxed);
+        
+        // 定期清理缓存
+        if should_cleanup {
+            self.cleanup_cache_and_stats();
         }
         
+        result
+    }
+    
+    async fn dispatch(
+        &self,
+        service: &str,
+        method: &str,
+        message: &Value,
+        stream_config: Option<StreamConfig>,
+        use_cache: bool
+    ) -> GrpcResult<Value> {
+        let cache_key = format!("{}:{}:{}", service, method, serde_json::to_string(message).unwrap_or_default());
+        let is_streaming = stream_config.is_some();
+        
         // 检查缓存（只对特定的只读方法）
-        if self.is_cacheable(method) {
-            if let Some(cached_value) = self.cache.get(&cache_key) {
+        // 流式请求需要真正建立订阅，不能使用缓存
+        let cacheable = !is_streaming && self.is_cacheable(method);
+        if cacheable && use_cache {
+            let cached_value = self.cache.lock().unwrap().get(&cache_key);
+            if let Some(cached_value) = cached_value {
                 log_debug(&format!("Cache hit for {}:{}", service, method));
-                self.active_requests.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-                let duration = start_time.elapsed();
-                self.performance_stats.record_request(duration, true);
                 return Ok(cached_value);
             }
         }
         
         log_debug(&format!("Handling gRPC request: service={}, method={}", service, method));
         
+        // 尚未连接时，不依赖 cline-core 的请求立即在本地完成，不等待连接重试
+        let connected = self.get_channel().is_some();
+        if !connected {
+            if let Some(value) = self.handle_without_connection(service, method, message).await {
+                return Ok(value);
+            }
+        }
+        
         // 确保连接已建立
         if let Err(e) = self.ensure_connected().await {
             log_error(&format!("Failed to ensure connection: {}", e));
-            self.connection_failures += 1;
-            self.active_requests.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-            let duration = start_time.elapsed();
-            self.performance_stats.record_request(duration, false);
-            return Err(e);
+            let error = GrpcError::unavailable(format!("cline-core is unavailable: {}", e));
+            
+            // 已有的连接在健康检查中失效，同样尝试在本地完成
+            if connected {
+                if let Some(value) = self.handle_without_connection(service, method, message).await {
+                    return Ok(value);
+                }
+            }
+            return Err(error.into());
         }
         
         // 根据服务名称找到对应的处理器
-        let service_type = match self.parse_service_type(service) {
-            Ok(service_type) => service_type,
+        let service_type = self.parse_service_type(service)?;
+        let mut result = self.call_service(&service_type, service, method, message, stream_config.clone()).await;
+        
+        // 如果错误可能是由于连接问题，重新连接后再尝试一次
+        let should_retry = match &result {
+            Ok(_) => false,
             Err(e) => {
-                self.active_requests.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-                let duration = start_time.elapsed();
-                self.performance_stats.record_request(duration, false);
-                return Err(e);
+                log_error(&format!("Request failed: {}", e));
+                let connection_failures = self.record_failure();
+                self.is_connection_error(e) && connection_failures <= 2
             }
         };
+        if should_retry {
+            log_debug("Attempting to reconnect due to connection error");
+            self.state.write().unwrap().channel = None;
+            
+            if self.ensure_connected().await.is_ok() {
+                result = self.call_service(&service_type, service, method, message, stream_config).await;
+            }
+        }
         
-        let result = if let Some(service_handler) = self.services.get_mut(&service_type) {
-            // 尝试执行请求，如果失败则尝试重新连接
-            match service_handler.handle_request(method, message).await {
-                Ok(result) => {
-                    // 请求成功，重置失败计数器
-                    self.connection_failures = 0;
-                    self.last_successful_connection = Some(std::time::Instant::now());
-                    
-                    // 缓存结果（如果适用）
-                    if self.is_cacheable(method) {
-                        self.cache.put(cache_key, result.clone());
-                    }
-                    
-                    Ok(result)
-                }
-                Err(e) => {
-                    log_error(&format!("Request failed: {}", e));
-                    self.connection_failures += 1;
-                    
-                    // 如果错误可能是由于连接问题，尝试重新连接一次
-                    if self.is_connection_error(&e) && self.connection_failures <= 2 {
-                        log_debug("Attempting to reconnect due to connection error");
-                        self.channel = None;
-                        
-                        if let Ok(_) = self.ensure_connected().await {
-                            // 重新连接成功，再次尝试请求
-                            if let Some(service_handler) = self.services.get_mut(&service_type) {
-                                return match service_handler.handle_request(method, message).await {
-                                    Ok(result) => {
-                                        if self.is_cacheable(method) {
-                                            self.cache.put(cache_key, result.clone());
-                                        }
-                                        Ok(result)
-                                    }
-                                    Err(e) => Err(e)
-                                };
-                            }
-                        }
-                    }
-                    
-                    Err(e)
+        if result.is_ok() {
+            // 请求成功，重置失败计数器
+            self.record_success();
+            
+            if let Ok(ref value) = result {
+                let mut cache = self.cache.lock().unwrap();
+                if cacheable {
+                    cache.put(cache_key, value.clone());
+                } else if !is_streaming && self.invalidates_cache(method) {
+                    // 修改状态的请求成功后，已缓存的结果不再可靠
+                    cache.invalidate();
                 }
             }
-        } else {
-            log_error(&format!("Service not found: {}", service));
-            Ok(serde_json::json!({
-                "error": format!("Service {} not implemented", service)
-            }))
-        };
-        
-        // 记录性能统计
-        let duration = start_time.elapsed();
-        let success = result.is_ok();
-        if self.config.enable_performance_monitoring {
-            self.performance_stats.record_request(duration, success);
         }
         
-        // 减少活跃请求计数
-        self.active_requests.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-        
-        // 定期清理缓存
-        if self.performance_stats.should_cleanup() {
-            self.cleanup_cache_and_stats();
+        result
+    }
+    
+    // 本地模型列表、桌面端文件操作和浏览器探测不依赖 cline-core，启动期间也能返回
+    async fn handle_without_connection(&self, service: &str, method: &str, message: &Value) -> Option<Value> {
+        let service_type = match service {
+            "cline.ModelsService" if ModelsServiceHandler::is_local_models_method(method) => ServiceType::Models,
+            "cline.FileService" if self.config.desktop_files.is_some() => ServiceType::File,
+            "cline.BrowserService" if BrowserServiceHandler::is_local_method(method) => ServiceType::Browser,
+            _ => return None,
+        };
+        let mut handler = self.service_handler(&service_type)?;
+        handler.handle_request_with_config(method, message, None).await.ok()
+    }
+    
+    // 优先使用专门的服务处理器；没有处理器或处理器未实现的方法通过描述符动态转发
+    async fn call_service(
+        &self,
+        service_type: &ServiceType,
+        service: &str,
+        method: &str,
+        message: &Value,
+        stream_config: Option<StreamConfig>
+    ) -> GrpcResult<Value> {
+        if let Some(mut service_handler) = self.service_handler(service_type) {
+            let result = service_handler.handle_request_with_config(method, message, stream_config.clone()).await;
+            if !Self::is_unhandled(&result) {
+                return result;
+            }
         }
         
-        result
+        let channel = self.get_channel().ok_or("No active connection")?;
+        ReflectionHandler::new(channel)
+            .handle_request_with_config(service, method, message, stream_config)
+            .await
+    }
+    
+    // 只有处理器明确表示不处理该方法时才改用动态转发，cline-core 返回的 Unimplemented 不会重发
+    fn is_unhandled(result: &GrpcResult<Value>) -> bool {
+        match result {
+            Err(e) => e.downcast_ref::<UnhandledMethod>().is_some(),
+            Ok(_) => false,
+        }
     }
     
     // 判断方法是否可缓存
@@ -335,17 +527,29 @@ impl ClineGrpcClient {
         matches!(method, "getLatestState" | "getLatestMcpServers")
     }
     
+    // get/search/check/fetch 开头的查询方法（如 getOllamaModels、searchFiles）不会修改状态，不清空缓存
+    // 按驼峰边界匹配，checkpointRestore 之类的方法仍会清空缓存
+    fn invalidates_cache(&self, method: &str) -> bool {
+        let is_query = ["get", "search", "check", "fetch"].iter().any(|prefix| {
+            method
+                .strip_prefix(prefix)
+                .map_or(false, |rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+        });
+        !is_query
+    }
+    
     // 清理缓存和统计
-    fn cleanup_cache_and_stats(&mut self) {
-        let removed = self.cache.cleanup_expired();
+    fn cleanup_cache_and_stats(&self) {
+        let removed = self.cache.lock().unwrap().cleanup_expired();
         if removed > 0 {
             log_debug(&format!("Cleaned up {} expired cache entries", removed));
         }
         
         // 如果统计数据过多，重置统计
-        if self.performance_stats.request_count > 10000 {
+        let mut performance_stats = self.performance_stats.lock().unwrap();
+        if performance_stats.request_count > 10000 {
             log_debug("Resetting performance statistics");
-            self.performance_stats.reset();
+            performance_stats.reset();
         }
     }
     
@@ -373,24 +577,39 @@ impl ClineGrpcClient {
             "cline.CheckpointsService" => Ok(ServiceType::Checkpoints),
             "cline.SlashService" => Ok(ServiceType::Slash),
             "cline.WebService" => Ok(ServiceType::Web),
-            _ => Err(format!("Unknown service: {}", service).into()),
+            _ => Err(GrpcError::not_implemented(format!("Unknown service: {}", service)).into()),
         }
     }
     
-    pub fn get_channel(&self) -> Option<&Channel> {
-        self.channel.as_ref()
+    pub fn endpoint(&self) -> String {
+        self.state.read().unwrap().endpoint.clone()
+    }
+    
+    // cline-core 换到新端口后切换地址；旧连接作废，下一次请求时连接新地址
+    pub fn set_endpoint(&self, endpoint: &str) {
+        log_debug(&format!("Switching cline-core endpoint to {}", endpoint));
+        let mut state = self.state.write().unwrap();
+        state.endpoint = endpoint.to_string();
+        state.channel = None;
+        state.services = Self::create_services(None, &self.config);
+        state.last_connect_failure = None;
+    }
+    
+    pub fn get_channel(&self) -> Option<Channel> {
+        self.state.read().unwrap().channel.clone()
     }
     
     // 获取连接状态信息
     pub fn get_connection_info(&self) -> serde_json::Value {
+        let state = self.state.read().unwrap();
         serde_json::json!({
-            "connected": self.channel.is_some(),
-            "endpoint": self.config.endpoint,
-            "last_successful_connection": self.last_successful_connection
+            "connected": state.channel.is_some(),
+            "endpoint": state.endpoint,
+            "last_successful_connection": state.last_successful_connection
                 .map(|t| t.elapsed().as_secs()),
-            "connection_failures": self.connection_failures,
+            "connection_failures": state.connection_failures,
             "health_check_interval_secs": self.config.health_check_interval.as_secs(),
-            "active_requests": self.active_requests.load(std::sync::atomic::Ordering::Relaxed),
+            "active_requests": self.active_requests.load(Ordering::Relaxed),
             "max_concurrent_requests": self.config.max_concurrent_requests,
             "performance_monitoring_enabled": self.config.enable_performance_monitoring
         })
@@ -399,7 +618,7 @@ impl ClineGrpcClient {
     // 获取性能统计
     pub fn get_performance_stats(&self) -> serde_json::Value {
         if self.config.enable_performance_monitoring {
-            self.performance_stats.to_json()
+            self.performance_stats.lock().unwrap().to_json()
         } else {
             serde_json::json!({"monitoring_disabled": true})
         }
@@ -407,7 +626,7 @@ impl ClineGrpcClient {
     
     // 获取缓存统计
     pub fn get_cache_stats(&self) -> serde_json::Value {
-        self.cache.get_stats()
+        self.cache.lock().unwrap().get_stats()
     }
     
     // 获取完整统计信息
@@ -420,27 +639,31 @@ impl ClineGrpcClient {
     }
     
     // 手动重置连接
-    pub async fn reset_connection(&mut self) -> GrpcResult<()> {
+    pub async fn reset_connection(&self) -> GrpcResult<()> {
         log_debug("Manually resetting connection");
-        self.channel = None;
-        self.connection_failures = 0;
+        let _connecting = self.connecting.lock().await;
+        {
+            let mut state = self.state.write().unwrap();
+            state.channel = None;
+            state.connection_failures = 0;
+        }
         
         // 清理缓存和统计
-        self.cache.clear();
-        self.performance_stats.reset();
+        self.cache.lock().unwrap().clear();
+        self.performance_stats.lock().unwrap().reset();
         
-        self.connect().await
+        self.establish_connection().await
     }
     
     // 手动清理缓存
-    pub fn clear_cache(&mut self) {
+    pub fn clear_cache(&self) {
         log_debug("Manually clearing cache");
-        self.cache.clear();
+        self.cache.lock().unwrap().clear();
     }
     
     // 手动重置性能统计
-    pub fn reset_performance_stats(&mut self) {
+    pub fn reset_performance_stats(&self) {
         log_debug("Manually resetting performance statistics");
-        self.performance_stats.reset();
+        self.performance_stats.lock().unwrap().reset();
     }
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+// 返回给前端的错误码，前端根据错误码决定如何展示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    // cline-core 未启动、连接断开或请求过多
+    Unavailable,
+    Timeout,
+    // 服务或方法尚未实现，或离线模式下没有对应的数据
+    NotImplemented,
+    // 请求参数无法解析或取值无效
+    InvalidArgument,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Unavailable => "unavailable",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::NotImplemented => "not-implemented",
+            ErrorCode::InvalidArgument => "invalid-argument",
+            ErrorCode::Internal => "internal",
+        }
+    }
+
+    pub fn from_status_code(code: tonic::Code) -> Self {
+        match code {
+            tonic::Code::Unavailable | tonic::Code::ResourceExhausted => ErrorCode::Unavailable,
+            tonic::Code::DeadlineExceeded => ErrorCode::Timeout,
+            tonic::Code::Unimplemented => ErrorCode::NotImplemented,
+            tonic::Code::InvalidArgument | tonic::Code::OutOfRange => ErrorCode::InvalidArgument,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+// 带错误码的 gRPC 错误，可以放入 GrpcResult 的 Box<dyn Error> 中
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl GrpcError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unavailable, message)
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Timeout, message)
+    }
+
+    pub fn not_implemented(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotImplemented, message)
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidArgument, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn from_status(status: &tonic::Status, operation: &str) -> Self {
+        Self::new(
+            ErrorCode::from_status_code(status.code()),
+            format!("{} failed: {}", operation, status),
+        )
+    }
+
+    // 识别任意错误的错误码；没有使用 GrpcError 的错误按错误信息推断
+    pub fn classify(error: &(dyn Error + 'static)) -> Self {
+        if let Some(error) = error.downcast_ref::<GrpcError>() {
+            return error.clone();
+        }
+        if let Some(error) = error.downcast_ref::<UnhandledMethod>() {
+            return Self::not_implemented(error.to_string());
+        }
+        if let Some(status) = error.downcast_ref::<tonic::Status>() {
+            return Self::new(ErrorCode::from_status_code(status.code()), status.message());
+        }
+        if error.downcast_ref::<tonic::transport::Error>().is_some() {
+            return Self::unavailable(error.to_string());
+        }
+
+        let message = error.to_string();
+        let lower = message.to_lowercase();
+        let code = if lower.contains("timeout") || lower.contains("timed out") {
+            ErrorCode::Timeout
+        } else if lower.contains("connection")
+            || lower.contains("unavailable")
+            || lower.contains("refused")
+            || lower.contains("broken pipe")
+            || (lower.contains("no ") && lower.contains("client available"))
+        {
+            ErrorCode::Unavailable
+        } else if lower.contains("not implemented") || lower.contains("unknown service") {
+            ErrorCode::NotImplemented
+        } else if lower.contains("invalid") || lower.contains("failed to parse") {
+            ErrorCode::InvalidArgument
+        } else {
+            ErrorCode::Internal
+        };
+        Self::new(code, message)
+    }
+}
+
+impl fmt::Display for GrpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for GrpcError {}
+
+// 专门处理器不处理的方法，ClineGrpcClient 只在这种情况下改用描述符动态转发
+// 与 cline-core 返回的 Unimplemented 状态区分开，后者直接返回给调用方
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnhandledMethod {
+    pub service: String,
+    pub method: String,
+}
+
+impl UnhandledMethod {
+    pub fn new(service: impl Into<String>, method: impl Into<String>) -> Self {
+        Self { service: service.into(), method: method.into() }
+    }
+}
+
+impl fmt::Display for UnhandledMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} method {} not implemented yet", self.service, self.method)
+    }
+}
+
+impl Error for UnhandledMethod {}
@@ -6,19 +6,17 @@ use crate::grpc_client::{
     connection::{ClineGrpcClient, ConnectionConfig},
     types::{ServiceType, StreamConfig, StreamCallback, CacheConfig},
     utils::RetryConfig,
-    get_global_client,
 };
 use serde_json::{json, Value};
 use std::sync::Arc;
 use std::time::Duration;
 
-/// 基本用法示例：使用全局客户端发送简单请求
+/// 基本用法示例：使用共享客户端发送简单请求
 pub async fn basic_usage_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("=== 基本用法示例 ===");
     
-    // 获取全局客户端实例
-    let client = get_global_client().await;
-    let mut client = client.lock().await;
+    // 应用中使用托管状态里的共享客户端，克隆后共享同一个连接
+    let client = ClineGrpcClient::new();
     
     // 发送 UI 服务请求
     let response = client.handle_request(
@@ -63,9 +61,10 @@ pub async fn custom_configuration_example() -> Result<(), Box<dyn std::error::Er
         },
         enable_performance_monitoring: true,
         max_concurrent_requests: 50,
+        ..Default::default()
     };
     
-    let mut client = ClineGrpcClient::with_config(config);
+    let client = ClineGrpcClient::with_config(config);
     
     // 手动连接
     println!("正在连接到 gRPC 服务器...");
@@ -91,8 +90,7 @@ pub async fn custom_configuration_example() -> Result<(), Box<dyn std::error::Er
 pub async fn streaming_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("=== 流式处理示例 ===");
     
-    let client = get_global_client().await;
-    let mut client = client.lock().await;
+    let client = ClineGrpcClient::new();
     
     // 创建流式消息处理回调
     let message_count = Arc::new(std::sync::Mutex::new(0));
@@ -121,6 +119,10 @@ pub async fn streaming_example() -> Result<(), Box<dyn std::error::Error + Send
         enable_streaming: true,
         callback: Some(callback),
         max_messages: Some(10), // 最多处理10条消息
+        on_complete: None,
+        cancel: None,
+        queue: None,
+        started: Default::default(),
     };
     
     // 获取 UI 服务处理器并发送流式请求
@@ -146,8 +148,7 @@ pub async fn streaming_example() -> Result<(), Box<dyn std::error::Error + Send
 pub async fn error_handling_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("=== 错误处理示例 ===");
     
-    let client = get_global_client().await;
-    let mut client = client.lock().await;
+    let client = ClineGrpcClient::new();
     
     // 1. 处理服务不存在的错误
     match client.handle_request("invalid.Service", "test", &json!({})).await {
@@ -174,7 +175,7 @@ pub async fn error_handling_example() -> Result<(), Box<dyn std::error::Error +
         ..Default::default()
     };
     
-    let mut timeout_client = ClineGrpcClient::with_config(timeout_config);
+    let timeout_client = ClineGrpcClient::with_config(timeout_config);
     match timeout_client.connect().await {
         Ok(_) => println!("意外的连接成功"),
         Err(e) => println!("预期的超时错误: {}", e),
@@ -187,8 +188,7 @@ pub async fn error_handling_example() -> Result<(), Box<dyn std::error::Error +
 pub async fn performance_monitoring_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("=== 性能监控示例 ===");
     
-    let client = get_global_client().await;
-    let mut client = client.lock().await;
+    let client = ClineGrpcClient::new();
     
     // 发送一些请求来生成性能数据
     for i in 0..5 {
@@ -241,8 +241,7 @@ pub async fn performance_monitoring_example() -> Result<(), Box<dyn std::error::
 pub async fn caching_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("=== 缓存使用示例 ===");
     
-    let client = get_global_client().await;
-    let mut client = client.lock().await;
+    let client = ClineGrpcClient::new();
     
     println!("首次请求（会被缓存）:");
     let start_time = std::time::Instant::now();
@@ -297,13 +296,12 @@ pub async fn caching_example() -> Result<(), Box<dyn std::error::Error + Send +
 pub async fn concurrent_requests_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("=== 并发处理示例 ===");
     
-    let client = get_global_client().await;
+    let client = ClineGrpcClient::new();
     
     // 创建多个并发任务
     let tasks: Vec<_> = (0..5).map(|i| {
         let client = client.clone();
         tokio::spawn(async move {
-            let mut client = client.lock().await;
             let start_time = std::time::Instant::now();
             
             let response = client.handle_request(
@@ -344,7 +342,6 @@ pub async fn concurrent_requests_example() -> Result<(), Box<dyn std::error::Err
     println!("  平均耗时: {:?}", total_duration / 5);
     
     // 检查客户端状态
-    let client = client.lock().await;
     let connection_info = client.get_connection_info();
     println!("当前活跃请求数: {}", connection_info["active_requests"]);
     
@@ -387,6 +384,7 @@ pub async fn configuration_tuning_examples() -> Result<(), Box<dyn std::error::E
         },
         enable_performance_monitoring: false,        // 禁用监控减少开销
         max_concurrent_requests: 10,
+        ..Default::default()
     };
     
     let _low_latency_client = ClineGrpcClient::with_config(low_latency_config);
@@ -411,6 +409,7 @@ pub async fn configuration_tuning_examples() -> Result<(), Box<dyn std::error::E
         },
         enable_performance_monitoring: true,
         max_concurrent_requests: 20,
+        ..Default::default()
     };
     
     let _reliability_client = ClineGrpcClient::with_config(reliability_config);
@@ -436,9 +435,10 @@ pub async fn complete_application_example() -> Result<(), Box<dyn std::error::Er
         },
         enable_performance_monitoring: true,
         max_concurrent_requests: 50,
+        ..Default::default()
     };
     
-    let mut client = ClineGrpcClient::with_config(app_config);
+    let client = ClineGrpcClient::with_config(app_config);
     
     println!("1. 初始化应用...");
     
@@ -489,6 +489,10 @@ pub async fn complete_application_example() -> Result<(), Box<dyn std::error::Er
         enable_streaming: true,
         callback: Some(message_handler),
         max_messages: Some(5),
+        on_complete: None,
+        cancel: None,
+        queue: None,
+        started: Default::default(),
     };
     
     // 开始监听流式消息
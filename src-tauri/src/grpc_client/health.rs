@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+use tonic::transport::{Channel, Endpoint};
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
+
+use crate::grpc_client::{
+    types::GrpcResult,
+    utils::{log_debug, log_success},
+};
+
+// 单次健康检查请求的超时时间
+pub const HEALTH_CHECK_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+// 使用标准的 grpc.health.v1 协议检查服务是否处于 SERVING 状态
+// service 为空字符串时检查整个服务器
+pub async fn check_serving(channel: Channel, service: &str) -> GrpcResult<bool> {
+    let mut client = HealthClient::new(channel);
+    let request = tonic::Request::new(HealthCheckRequest {
+        service: service.to_string(),
+    });
+
+    let response = tokio::time::timeout(HEALTH_CHECK_REQUEST_TIMEOUT, client.check(request))
+        .await
+        .map_err(|_| "Health check timeout")?
+        .map_err(|e| format!("Health check failed: {}", e))?;
+
+    Ok(response.into_inner().status == ServingStatus::Serving as i32)
+}
+
+// 轮询 endpoint 直到服务报告 SERVING，返回等待的时长；超过 timeout 返回错误
+pub async fn wait_until_serving(
+    endpoint: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> GrpcResult<Duration> {
+    let started_at = Instant::now();
+    let endpoint = Endpoint::from_shared(endpoint.to_string())
+        .map_err(|e| format!("Invalid endpoint: {}", e))?
+        .connect_timeout(HEALTH_CHECK_REQUEST_TIMEOUT);
+    let mut last_error = String::from("not serving");
+
+    log_debug(&format!("Waiting for gRPC health check at {} to report SERVING", endpoint.uri()));
+
+    while started_at.elapsed() < timeout {
+        match endpoint.connect().await {
+            Ok(channel) => match check_serving(channel, "").await {
+                Ok(true) => {
+                    let elapsed = started_at.elapsed();
+                    log_success(&format!("gRPC server is serving after {}ms", elapsed.as_millis()));
+                    return Ok(elapsed);
+                }
+                Ok(false) => last_error = "not serving".to_string(),
+                Err(e) => last_error = e.to_string(),
+            },
+            Err(e) => last_error = format!("Connection error: {}", e),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+
+    Err(format!(
+        "gRPC server did not become ready within {}s (last error: {})",
+        timeout.as_secs(),
+        last_error
+    )
+    .into())
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+use serde_json::Value;
+
+use crate::grpc_client::{
+    error::GrpcError,
+    types::GrpcResult,
+    utils::log_debug,
+};
+use crate::settings::LocalModelsSettings;
+
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+pub const DEFAULT_LM_STUDIO_BASE_URL: &str = "http://localhost:1234";
+// 本地服务没有运行时连接会很快失败，这里只防止服务卡住
+pub const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_millis(1500);
+
+// 直接查询本机运行的 Ollama 和 LM Studio，不依赖 cline-core
+#[derive(Debug, Clone)]
+pub struct LocalModelDiscovery {
+    ollama_base_url: String,
+    lm_studio_base_url: String,
+    http: reqwest::Client,
+}
+
+impl Default for LocalModelDiscovery {
+    fn default() -> Self {
+        Self::new(DEFAULT_OLLAMA_BASE_URL, DEFAULT_LM_STUDIO_BASE_URL, DEFAULT_DISCOVERY_TIMEOUT)
+    }
+}
+
+impl LocalModelDiscovery {
+    pub fn new(ollama_base_url: &str, lm_studio_base_url: &str, timeout: Duration) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .connect_timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            ollama_base_url: ollama_base_url.to_string(),
+            lm_studio_base_url: lm_studio_base_url.to_string(),
+            http,
+        }
+    }
+
+    pub fn from_settings(settings: &LocalModelsSettings) -> Self {
+        Self::new(
+            settings.ollama_base_url.as_deref().unwrap_or(DEFAULT_OLLAMA_BASE_URL),
+            settings.lm_studio_base_url.as_deref().unwrap_or(DEFAULT_LM_STUDIO_BASE_URL),
+            Duration::from_millis(settings.timeout_ms),
+        )
+    }
+
+    // base_url 为请求中携带的地址（前端设置的 ollamaBaseUrl），为空时使用默认地址
+    pub async fn ollama_models(&self, base_url: Option<&str>) -> GrpcResult<Vec<String>> {
+        let base_url = base_url.unwrap_or(&self.ollama_base_url);
+        let tags = self.get_json(base_url, "/api/tags").await?;
+        let names = tags["models"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["name"].as_str().or_else(|| model["model"].as_str()));
+        Ok(merge_model_lists(Vec::new(), names.map(|name| name.to_string()).collect()))
+    }
+
+    pub async fn lm_studio_models(&self, base_url: Option<&str>) -> GrpcResult<Vec<String>> {
+        let base_url = base_url.unwrap_or(&self.lm_studio_base_url);
+        let models = self.get_json(base_url, "/v1/models").await?;
+        let ids = models["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["id"].as_str());
+        Ok(merge_model_lists(Vec::new(), ids.map(|id| id.to_string()).collect()))
+    }
+
+    async fn get_json(&self, base_url: &str, path: &str) -> GrpcResult<Value> {
+        let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+        log_debug(&format!("Discovering local models at {}", url));
+
+        let response = self.http.get(&url).send().await.map_err(|e| {
+            if e.is_timeout() {
+                GrpcError::timeout(format!("{} timeout", url))
+            } else if e.is_connect() {
+                GrpcError::unavailable(format!("{} is not reachable: {}", url, e))
+            } else {
+                GrpcError::internal(format!("Failed to query {}: {}", url, e))
+            }
+        })?;
+        if !response.status().is_success() {
+            return Err(GrpcError::unavailable(format!("{} returned {}", url, response.status())).into());
+        }
+        Ok(response
+            .json::<Value>()
+            .await
+            .map_err(|e| GrpcError::internal(format!("Invalid response from {}: {}", url, e)))?)
+    }
+}
+
+// cline-core 的结果在前，本地发现的新模型追加在后，去掉重复项
+pub fn merge_model_lists(core: Vec<String>, local: Vec<String>) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::with_capacity(core.len() + local.len());
+    for model in core.into_iter().chain(local) {
+        if !model.is_empty() && !merged.contains(&model) {
+            merged.push(model);
+        }
+    }
+    merged
+}
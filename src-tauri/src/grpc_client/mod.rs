@@ -1,4 +1,9 @@
+pub mod browser_discovery;
 pub mod connection;
+pub mod error;
+pub mod health;
+pub mod local_models;
+pub mod reflection;
 pub mod services;
 pub mod types;
 pub mod utils;
@@ -9,6 +14,14 @@ mod tests;
 mod tests_utils;
 #[cfg(test)]
 mod tests_performance;
+#[cfg(test)]
+mod tests_reflection;
+#[cfg(test)]
+mod tests_local_models;
+#[cfg(test)]
+mod tests_browser_discovery;
+#[cfg(test)]
+mod tests_forwarding;
 
 // 导入生成的 protobuf 代码
 pub mod cline {
@@ -17,16 +30,5 @@ pub mod cline {
 
 // 重新导出公共接口
 pub use connection::ClineGrpcClient;
+pub use error::{ErrorCode, GrpcError};
 pub use types::*;
-
-// 使用简单的 Arc 共享客户端，无需锁
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-lazy_static::lazy_static! {
-    static ref GLOBAL_CLIENT: Arc<RwLock<ClineGrpcClient>> = Arc::new(RwLock::new(ClineGrpcClient::new()));
-}
-
-pub async fn get_global_client() -> Arc<RwLock<ClineGrpcClient>> {
-    GLOBAL_CLIENT.clone()
-}
\ No newline at end of file
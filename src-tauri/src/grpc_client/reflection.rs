@@ -0,0 +1,211 @@
+use std::sync::OnceLock;
+use prost::{Message, Name};
+use prost_reflect::{DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, MethodDescriptor, SerializeOptions};
+use serde_json::Value;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Channel;
+use tonic::{Request, Status, Streaming};
+
+use crate::grpc_client::{
+    error::GrpcError,
+    types::{GrpcResult, StreamConfig},
+    utils::{with_timeout, log_debug, log_success, spawn_stream_task, DEFAULT_REQUEST_TIMEOUT},
+};
+
+// build.rs 编译 cline/*.proto 时输出的描述符集合
+const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/cline_descriptor.bin"));
+
+static DESCRIPTOR_POOL: OnceLock<DescriptorPool> = OnceLock::new();
+
+pub fn descriptor_pool() -> &'static DescriptorPool {
+    DESCRIPTOR_POOL.get_or_init(|| {
+        DescriptorPool::decode(FILE_DESCRIPTOR_SET).expect("cline descriptor set must be valid")
+    })
+}
+
+// 按服务全名（如 cline.StateService）和方法名查找方法描述
+pub fn find_method(service: &str, method: &str) -> Result<MethodDescriptor, GrpcError> {
+    let service_descriptor = descriptor_pool()
+        .get_service_by_name(service)
+        .ok_or_else(|| GrpcError::not_implemented(format!("Unknown service: {}", service)))?;
+
+    // proto 中的方法名是 camelCase，兼容前端传入首字母大写的写法
+    let method_descriptor = service_descriptor
+        .methods()
+        .find(|m| m.name() == method)
+        .or_else(|| service_descriptor.methods().find(|m| m.name().eq_ignore_ascii_case(method)));
+    method_descriptor.ok_or_else(|| GrpcError::not_implemented(format!("Unknown method: {}.{}", service, method)))
+}
+
+// 按 proto3 JSON 规则把请求转换为 protobuf 消息，忽略未知字段
+pub fn json_to_message(descriptor: MessageDescriptor, json: &Value) -> Result<DynamicMessage, GrpcError> {
+    let options = DeserializeOptions::new().deny_unknown_fields(false);
+    DynamicMessage::deserialize_with_options(descriptor.clone(), json, &options).map_err(|e| {
+        GrpcError::invalid_argument(format!("Invalid {} message: {}", descriptor.full_name(), e))
+    })
+}
+
+// 按 proto3 JSON 规则转换响应，字段使用 json_name 并保留默认值
+pub fn message_to_json(message: &DynamicMessage) -> Result<Value, GrpcError> {
+    let options = SerializeOptions::new().skip_default_fields(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(|e| GrpcError::internal(format!("Failed to convert {} to JSON: {}", message.descriptor().full_name(), e)))
+}
+
+pub fn message_descriptor(full_name: &str) -> Result<MessageDescriptor, GrpcError> {
+    descriptor_pool()
+        .get_message_by_name(full_name)
+        .ok_or_else(|| GrpcError::internal(format!("Unknown message type: {}", full_name)))
+}
+
+// 生成的 prost 类型按 proto3 JSON 规则转换为前端 TypeScript proto 客户端期望的格式
+pub fn to_json<T: Message + Name>(message: &T) -> Result<Value, GrpcError> {
+    let descriptor = message_descriptor(&T::full_name())?;
+    let dynamic = DynamicMessage::decode(descriptor, message.encode_to_vec().as_slice())
+        .map_err(|e| GrpcError::internal(format!("Failed to decode {}: {}", T::full_name(), e)))?;
+    message_to_json(&dynamic)
+}
+
+// 按 proto3 JSON 规则解析前端发来的请求
+pub fn from_json<T: Message + Name + Default>(json: &Value) -> Result<T, GrpcError> {
+    let dynamic = json_to_message(message_descriptor(&T::full_name())?, json)?;
+    T::decode(dynamic.encode_to_vec().as_slice())
+        .map_err(|e| GrpcError::internal(format!("Failed to decode {}: {}", T::full_name(), e)))
+}
+
+// 基于描述符编解码 DynamicMessage 的 tonic 编解码器
+#[derive(Debug, Clone)]
+pub struct DynamicCodec {
+    response: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    pub fn new(response: MessageDescriptor) -> Self {
+        Self { response }
+    }
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder { descriptor: self.response.clone() }
+    }
+}
+
+#[derive(Debug)]
+pub struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst).map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct DynamicDecoder {
+    descriptor: MessageDescriptor,
+}
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let mut message = DynamicMessage::new(self.descriptor.clone());
+        message.merge(src).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Some(message))
+    }
+}
+
+// 没有专门处理器的 cline.* 方法通过描述符动态转发给 cline-core
+#[derive(Debug, Clone)]
+pub struct ReflectionHandler {
+    channel: Channel,
+}
+
+impl ReflectionHandler {
+    pub fn new(channel: Channel) -> Self {
+        Self { channel }
+    }
+
+    pub async fn handle_request_with_config(
+        &self,
+        service: &str,
+        method: &str,
+        message: &Value,
+        stream_config: Option<StreamConfig>,
+    ) -> GrpcResult<Value> {
+        let method_descriptor = find_method(service, method)?;
+        if method_descriptor.is_client_streaming() {
+            return Err(GrpcError::not_implemented(format!("Client streaming method {}.{} is not supported", service, method)).into());
+        }
+
+        let request = json_to_message(method_descriptor.input(), message)?;
+        let path = PathAndQuery::try_from(format!("/{}/{}", service, method_descriptor.name()))
+            .map_err(|e| GrpcError::invalid_argument(format!("Invalid method path: {}", e)))?;
+        let operation = format!("{}.{}", service, method_descriptor.name());
+        log_debug(&format!("Forwarding {} through reflection passthrough", operation));
+
+        let mut grpc = tonic::client::Grpc::new(self.channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| GrpcError::unavailable(format!("Service was not ready: {}", e)))?;
+        let codec = DynamicCodec::new(method_descriptor.output());
+
+        if !method_descriptor.is_server_streaming() {
+            let response = with_timeout(grpc.unary(Request::new(request), path, codec), DEFAULT_REQUEST_TIMEOUT, &operation).await?;
+            log_success(&format!("Received {} response", operation));
+            return Ok(message_to_json(&response.into_inner())?);
+        }
+
+        let mut stream = grpc
+            .server_streaming(Request::new(request), path, codec)
+            .await
+            .map_err(|status| GrpcError::from_status(&status, &operation))?
+            .into_inner();
+        log_success(&format!("Established {} stream", operation));
+
+        match stream_config {
+            // 流式请求的每条消息都转发给调用方
+            Some(config) if config.enable_streaming => {
+                spawn_stream_task(config.clone(), Self::forward_stream(stream, config, operation));
+                Ok(serde_json::json!({}))
+            }
+            // 非流式调用只返回第一条消息
+            _ => match stream.message().await {
+                Ok(Some(first)) => Ok(message_to_json(&first)?),
+                Ok(None) => Ok(serde_json::json!({})),
+                Err(status) => Err(GrpcError::from_status(&status, &operation).into()),
+            },
+        }
+    }
+
+    async fn forward_stream(mut stream: Streaming<DynamicMessage>, config: StreamConfig, operation: String) -> GrpcResult<()> {
+        let max_messages = config.max_messages.unwrap_or(usize::MAX);
+        let mut message_count = 0;
+
+        while let Some(message) = stream.message().await.map_err(|status| GrpcError::from_status(&status, &operation))? {
+            config.send(message_to_json(&message)?).await;
+            message_count += 1;
+            if message_count >= max_messages {
+                break;
+            }
+        }
+
+        log_success(&format!("{} stream completed, forwarded {} messages", operation, message_count));
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use serde_json::Value;
+use tonic::transport::Channel;
+use crate::grpc_client::{
+    browser_discovery::{find_chrome, ChromeDiscovery, DEFAULT_DEBUG_ENDPOINTS},
+    cline::browser_service_client::BrowserServiceClient,
+    error::UnhandledMethod,
+    types::{GrpcResult, StreamConfig},
+    utils::{log_debug, log_success},
+};
+
+#[derive(Debug, Clone)]
+pub struct BrowserServiceHandler {
+    client: Option<BrowserServiceClient<Channel>>,
+    chrome: ChromeDiscovery,
+}
+
+impl BrowserServiceHandler {
+    pub fn new() -> Self {
+        Self::with_discovery(ChromeDiscovery::default())
+    }
+
+    pub fn with_discovery(chrome: ChromeDiscovery) -> Self {
+        Self { client: None, chrome }
+    }
+
+    pub fn set_client(&mut self, channel: Channel) {
+        self.client = Some(BrowserServiceClient::new(channel));
+    }
+
+    // 这些方法可以只依靠本机探测返回结果
+    pub fn is_local_method(method: &str) -> bool {
+        matches!(method, "getDetectedChromePath" | "testBrowserConnection" | "discoverBrowser")
+    }
+
+    // 未连接 cline-core 时使用本机探测，返回 None 表示本机也无法给出结果
+    async fn handle_locally(&self, method: &str, message: &Value) -> Option<Value> {
+        let result = match method {
+            "getDetectedChromePath" => {
+                let path = find_chrome()?;
+                serde_json::json!({ "path": path.to_string_lossy(), "isBundled": false })
+            }
+            "testBrowserConnection" => {
+                let endpoint = message
+                    .get("value")
+                    .and_then(|v| v.as_str())
+                    .filter(|v| !v.is_empty())
+                    .unwrap_or(DEFAULT_DEBUG_ENDPOINTS[0]);
+                self.chrome.test_connection(endpoint).await
+            }
+            "discoverBrowser" => self.chrome.discover().await?,
+            _ => return None,
+        };
+
+        log_success(&format!("[BrowserService] Handled {} locally", method));
+        Some(result)
+    }
+
+    pub async fn handle_request(&mut self, method: &str, message: &Value) -> GrpcResult<Value> {
+        self.handle_request_with_config(method, message, None).await
+    }
+
+    pub async fn handle_request_with_config(
+        &mut self,
+        method: &str,
+        message: &Value,
+        _stream_config: Option<StreamConfig>
+    ) -> GrpcResult<Value> {
+        log_debug(&format!("Processing BrowserService.{} request", method));
+
+        if self.client.is_none() {
+            if let Some(result) = self.handle_locally(method, message).await {
+                return Ok(result);
+            }
+        }
+
+        let client = self.client.as_mut().ok_or("No BrowserService gRPC client available");
+        match method {
+            // cline-core 没有找到 Chrome 时再使用本机探测的结果
+            "getDetectedChromePath" => {
+                let response: GrpcResult<Value> = forward_unary!(client, get_detected_chrome_path, method, message);
+                let response = response?;
+                let detected = response.get("path").and_then(|v| v.as_str()).map_or(false, |v| !v.is_empty());
+                if !detected {
+                    if let Some(result) = self.handle_locally(method, message).await {
+                        return Ok(result);
+                    }
+                }
+                Ok(response)
+            }
+            _ => {
+                log_debug(&format!("BrowserService method not implemented: {}", method));
+                Err(UnhandledMethod::new("BrowserService", method).into())
+            }
+        }
+    }
+}
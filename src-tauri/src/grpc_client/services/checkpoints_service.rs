@@ -0,0 +1,100 @@
+use serde_json::Value;
+use tonic::transport::Channel;
+use crate::grpc_client::{
+    cline::checkpoints_service_client::CheckpointsServiceClient,
+    error::{GrpcError, UnhandledMethod},
+    types::{GrpcResult, StreamConfig},
+    utils::log_debug,
+};
+
+// cline-core 支持的恢复方式
+pub const RESTORE_TYPES: [&str; 3] = ["task", "workspace", "taskAndWorkspace"];
+
+#[derive(Debug, Clone)]
+pub struct CheckpointsServiceHandler {
+    client: Option<CheckpointsServiceClient<Channel>>,
+}
+
+impl CheckpointsServiceHandler {
+    pub fn new() -> Self {
+        Self { client: None }
+    }
+
+    pub fn set_client(&mut self, channel: Channel) {
+        self.client = Some(CheckpointsServiceClient::new(channel));
+    }
+
+    // 检查点以消息的 ts 标识，缺失时 cline-core 会静默地使用 0
+    fn validate_request(method: &str, message: &Value) -> Result<(), GrpcError> {
+        let (field, value) = match method {
+            "checkpointDiff" => ("value", &message["value"]),
+            "checkpointRestore" => ("number", &message["number"]),
+            _ => return Ok(()),
+        };
+        let is_number = value.as_i64().is_some() || value.as_str().map_or(false, |v| v.parse::<i64>().is_ok());
+        if !is_number {
+            return Err(GrpcError::invalid_argument(format!(
+                "CheckpointsService.{} requires the checkpoint message ts in \"{}\"", method, field
+            )));
+        }
+
+        if method == "checkpointRestore" {
+            let restore_type = message["restoreType"].as_str().unwrap_or_default();
+            if !RESTORE_TYPES.contains(&restore_type) {
+                return Err(GrpcError::invalid_argument(format!(
+                    "Invalid restoreType {:?}, expected one of {}", restore_type, RESTORE_TYPES.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn handle_request(&mut self, method: &str, message: &Value) -> GrpcResult<Value> {
+        self.handle_request_with_config(method, message, None).await
+    }
+
+    pub async fn handle_request_with_config(
+        &mut self,
+        method: &str,
+        message: &Value,
+        _stream_config: Option<StreamConfig>
+    ) -> GrpcResult<Value> {
+        log_debug(&format!("Processing CheckpointsService.{} request", method));
+        Self::validate_request(method, message)?;
+
+        let client = self.client.as_mut().ok_or("No CheckpointsService gRPC client available");
+        match method {
+            "checkpointDiff" => forward_unary!(client, checkpoint_diff, method, message),
+            "checkpointRestore" => forward_unary!(client, checkpoint_restore, method, message),
+            _ => {
+                log_debug(&format!("CheckpointsService method not implemented: {}", method));
+                Err(UnhandledMethod::new("CheckpointsService", method).into())
+            }
+        }
+    }
+}
+
+// 从 State 消息的 stateJson 中取出当前任务的检查点，按时间顺序排列
+pub fn checkpoints_from_state(state_json: &str) -> Result<Value, GrpcError> {
+    let state: Value = serde_json::from_str(state_json)
+        .map_err(|e| GrpcError::internal(format!("Invalid stateJson: {}", e)))?;
+
+    let checkpoints: Vec<Value> = state["clineMessages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|message| message["lastCheckpointHash"].as_str().map_or(false, |hash| !hash.is_empty()))
+        .map(|message| serde_json::json!({
+            "ts": message["ts"],
+            "hash": message["lastCheckpointHash"],
+            "say": message["say"],
+            "isCheckpointCheckedOut": message["isCheckpointCheckedOut"].as_bool().unwrap_or(false),
+            "conversationHistoryIndex": message["conversationHistoryIndex"],
+        }))
+        .collect();
+
+    Ok(serde_json::json!({
+        "taskId": state["currentTaskItem"]["id"],
+        "checkpoints": checkpoints,
+    }))
+}
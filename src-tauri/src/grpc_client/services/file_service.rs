@@ -0,0 +1,294 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use base64::Engine;
+use serde_json::Value;
+use tonic::transport::Channel;
+use crate::grpc_client::{
+    cline::file_service_client::FileServiceClient,
+    error::{GrpcError, UnhandledMethod},
+    types::{GrpcResult, StreamConfig},
+    utils::{log_debug, log_success},
+};
+
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+// 本地搜索时跳过的目录
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "target", "dist"];
+// 本地搜索最多遍历的条目数，避免在超大目录中卡住
+const MAX_SEARCH_ENTRIES: usize = 50_000;
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+// 由桌面端直接完成的文件操作，grpc_client 不直接依赖 Tauri
+#[async_trait::async_trait]
+pub trait DesktopFileOps: Send + Sync + std::fmt::Debug {
+    // 当前工作区根目录
+    fn workspace_root(&self) -> Option<PathBuf>;
+    // 用系统默认程序打开文件或目录
+    async fn open_path(&self, path: &Path) -> GrpcResult<()>;
+    async fn open_url(&self, url: &str) -> GrpcResult<()>;
+    // 打开文件选择对话框，取消时返回空列表
+    async fn pick_files(&self, images_only: bool) -> GrpcResult<Vec<PathBuf>>;
+    // 用户曾在对话框中选择过该文件，即使在工作区之外也允许打开
+    fn is_user_selected(&self, path: &Path) -> bool;
+    async fn write_clipboard(&self, text: &str) -> GrpcResult<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct FileServiceHandler {
+    client: Option<FileServiceClient<Channel>>,
+    desktop: Option<Arc<dyn DesktopFileOps>>,
+}
+
+impl FileServiceHandler {
+    pub fn new() -> Self {
+        Self::with_desktop(None)
+    }
+
+    pub fn with_desktop(desktop: Option<Arc<dyn DesktopFileOps>>) -> Self {
+        Self { client: None, desktop }
+    }
+
+    pub fn set_client(&mut self, channel: Channel) {
+        self.client = Some(FileServiceClient::new(channel));
+    }
+
+    // 能在桌面端完成的请求直接处理，返回 None 时转发给 cline-core
+    // 工作区搜索只在 cline-core 未连接时使用，连接后由 cline-core 基于 ripgrep 和 .gitignore 搜索
+    async fn handle_natively(desktop: &dyn DesktopFileOps, method: &str, message: &Value, connected: bool) -> Option<GrpcResult<Value>> {
+        let value = message.get("value").and_then(|v| v.as_str()).unwrap_or_default();
+        let root = desktop.workspace_root();
+
+        let result = match method {
+            "openFile" if !value.is_empty() => Self::open_checked_path(desktop, root.as_deref(), value).await,
+            "openMention" if is_url(value) => desktop.open_url(value).await.map(|_| serde_json::json!({})),
+            // 文件和目录提及以 / 开头，problems、terminal 等交给 cline-core
+            "openMention" if value.starts_with('/') => Self::open_checked_path(desktop, root.as_deref(), value).await,
+            "copyToClipboard" => desktop.write_clipboard(value).await.map(|_| serde_json::json!({})),
+            "selectImages" => Self::select_images(desktop).await,
+            "selectFiles" => Self::select_files(desktop, message.get("value").and_then(|v| v.as_bool()).unwrap_or(true)).await,
+            "searchFiles" if !connected => search_in_background(root?, message.clone()).await,
+            "getRelativePaths" => {
+                let root = root?;
+                let uris = message["uris"].as_array().into_iter().flatten().filter_map(|uri| uri.as_str());
+                Ok(serde_json::json!({
+                    "paths": uris.map(|uri| relative_path(&root, uri)).collect::<Vec<_>>()
+                }))
+            }
+            _ => return None,
+        };
+
+        if result.is_ok() {
+            log_success(&format!("[FileService] Handled {} natively", method));
+        }
+        Some(result)
+    }
+
+    // 提及和模型生成的路径可能包含 ..，只打开工作区内或用户选择过的文件
+    async fn open_checked_path(desktop: &dyn DesktopFileOps, root: Option<&Path>, value: &str) -> GrpcResult<Value> {
+        let path = resolve_workspace_path(root, value);
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| GrpcError::invalid_argument(format!("Cannot open {}: {}", path.display(), e)))?;
+        let in_workspace = root
+            .and_then(|root| root.canonicalize().ok())
+            .map_or(false, |root| canonical.starts_with(root));
+        if !in_workspace && !desktop.is_user_selected(&canonical) {
+            return Err(GrpcError::invalid_argument(format!("{} is outside the workspace", path.display())).into());
+        }
+
+        desktop.open_path(&canonical).await?;
+        Ok(serde_json::json!({}))
+    }
+
+    async fn select_images(desktop: &dyn DesktopFileOps) -> GrpcResult<Value> {
+        let images = read_image_data_urls(desktop.pick_files(true).await?).await?;
+        Ok(serde_json::json!({ "values": images }))
+    }
+
+    // 图片以 data URL 返回，其余文件返回路径
+    async fn select_files(desktop: &dyn DesktopFileOps, images_allowed: bool) -> GrpcResult<Value> {
+        let (images, files): (Vec<PathBuf>, Vec<PathBuf>) = desktop
+            .pick_files(false)
+            .await?
+            .into_iter()
+            .partition(|path| image_mime_type(path).is_some());
+        let images = if images_allowed { read_image_data_urls(images).await? } else { Vec::new() };
+        let files: Vec<String> = files.iter().map(|path| path.to_string_lossy().to_string()).collect();
+        Ok(serde_json::json!({ "values1": images, "values2": files }))
+    }
+
+    pub async fn handle_request(&mut self, method: &str, message: &Value) -> GrpcResult<Value> {
+        self.handle_request_with_config(method, message, None).await
+    }
+
+    pub async fn handle_request_with_config(
+        &mut self,
+        method: &str,
+        message: &Value,
+        _stream_config: Option<StreamConfig>
+    ) -> GrpcResult<Value> {
+        log_debug(&format!("Processing FileService.{} request", method));
+
+        if let Some(desktop) = self.desktop.clone() {
+            if let Some(result) = Self::handle_natively(desktop.as_ref(), method, message, self.client.is_some()).await {
+                return result;
+            }
+        }
+
+        let client = self.client.as_mut().ok_or("No FileService gRPC client available");
+        match method {
+            "openFile" => forward_unary!(client, open_file, method, message),
+            "openImage" => forward_unary!(client, open_image, method, message),
+            "openMention" => forward_unary!(client, open_mention, method, message),
+            "copyToClipboard" => forward_unary!(client, copy_to_clipboard, method, message),
+            "searchFiles" => forward_unary!(client, search_files, method, message),
+            "searchCommits" => forward_unary!(client, search_commits, method, message),
+            "getRelativePaths" => forward_unary!(client, get_relative_paths, method, message),
+            // 规则文件
+            "createRuleFile" => forward_unary!(client, create_rule_file, method, message),
+            "deleteRuleFile" => forward_unary!(client, delete_rule_file, method, message),
+            "toggleClineRule" => forward_unary!(client, toggle_cline_rule, method, message),
+            "refreshRules" => forward_unary!(client, refresh_rules, method, message),
+            _ => {
+                log_debug(&format!("FileService method not implemented: {}", method));
+                Err(UnhandledMethod::new("FileService", method).into())
+            }
+        }
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+// 提及中的路径相对于工作区，/src/main.rs 指工作区下的 src/main.rs；已存在的绝对路径保持不变
+// 只做路径拼接，打开之前还需检查解析后的路径是否位于工作区内
+pub fn resolve_workspace_path(root: Option<&Path>, path: &str) -> PathBuf {
+    let path = PathBuf::from(path.strip_prefix("file://").unwrap_or(path));
+    match root {
+        Some(root) if !(path.is_absolute() && path.exists()) => root.join(path.to_string_lossy().trim_start_matches('/')),
+        _ => path,
+    }
+}
+
+// 工作区内的文件返回使用 / 分隔的相对路径，工作区外的文件保留绝对路径
+pub fn relative_path(root: &Path, uri: &str) -> String {
+    let path = Path::new(uri.strip_prefix("file://").unwrap_or(uri));
+    match path.strip_prefix(root) {
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+pub fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "webp" => Some("image/webp"),
+        "gif" => Some("image/gif"),
+        _ => None,
+    }
+}
+
+pub fn image_data_url(path: &Path) -> GrpcResult<String> {
+    let mime_type = image_mime_type(path)
+        .ok_or_else(|| GrpcError::invalid_argument(format!("{} is not a supported image", path.display())))?;
+    let bytes = std::fs::read(path).map_err(|e| GrpcError::internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    Ok(format!("data:{};base64,{}", mime_type, base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+// 遍历目录是阻塞操作，放到阻塞线程池中执行
+async fn search_in_background(root: PathBuf, message: Value) -> GrpcResult<Value> {
+    tokio::task::spawn_blocking(move || search_files_response(&root, &message))
+        .await
+        .map_err(|e| GrpcError::internal(format!("Workspace search failed: {}", e)).into())
+}
+
+// 在阻塞线程池中读取图片，避免大文件阻塞异步运行时的工作线程
+async fn read_image_data_urls(paths: Vec<PathBuf>) -> GrpcResult<Vec<String>> {
+    tokio::task::spawn_blocking(move || paths.iter().map(|path| image_data_url(path)).collect())
+        .await
+        .map_err(|e| GrpcError::internal(format!("Failed to read images: {}", e)))?
+}
+
+// 在工作区中按路径搜索文件和目录，查询中的每个词都要出现在相对路径中
+pub fn search_workspace(root: &Path, query: &str, folders_only: Option<bool>, limit: usize) -> Vec<(String, bool)> {
+    let terms: Vec<String> = query.split_whitespace().map(|term| term.to_lowercase()).collect();
+    let mut matches = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    let mut visited = 0;
+
+    while let Some(dir) = pending.pop() {
+        if visited > MAX_SEARCH_ENTRIES {
+            break;
+        }
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            visited += 1;
+            if visited > MAX_SEARCH_ENTRIES {
+                break;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let is_dir = entry.file_type().map_or(false, |file_type| file_type.is_dir());
+            if is_dir {
+                if SKIPPED_DIRS.contains(&name.as_str()) {
+                    continue;
+                }
+                pending.push(entry.path());
+            }
+            if folders_only.map_or(false, |folders_only| folders_only != is_dir) {
+                continue;
+            }
+
+            let relative = relative_path(root, &entry.path().to_string_lossy());
+            let lowercase = relative.to_lowercase();
+            if terms.iter().all(|term| lowercase.contains(term)) {
+                matches.push((relative, is_dir));
+            }
+        }
+    }
+
+    // 路径越短越可能是要找的文件
+    matches.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(&b.0)));
+    matches.truncate(limit);
+    matches
+}
+
+fn search_files_response(root: &Path, message: &Value) -> Value {
+    let query = message["query"].as_str().unwrap_or_default();
+    let limit = message["limit"].as_u64().map_or(DEFAULT_SEARCH_LIMIT, |limit| limit as usize);
+    let folders_only = match message["selectedType"].as_str() {
+        Some("FOLDER") => Some(true),
+        Some("FILE") => Some(false),
+        _ => None,
+    };
+
+    let results: Vec<Value> = search_workspace(root, query, folders_only, limit)
+        .into_iter()
+        .map(|(path, is_dir)| {
+            let label = path.rsplit('/').next().unwrap_or_default().to_string();
+            serde_json::json!({
+                "path": path,
+                "type": if is_dir { "folder" } else { "file" },
+                "label": label,
+            })
+        })
+        .collect();
+
+    let mut response = serde_json::json!({ "results": results });
+    if let Some(request_id) = message.get("mentionsRequestId") {
+        response["mentionsRequestId"] = request_id.clone();
+    }
+    response
+}
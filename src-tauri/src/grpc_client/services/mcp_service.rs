@@ -4,11 +4,13 @@ use serde_json::Value;
 
 use crate::grpc_client::{
     cline::{mcp_service_client::McpServiceClient, EmptyRequest, Metadata, Empty},
+    error::UnhandledMethod,
+    reflection::to_json,
     types::{GrpcResult, StreamConfig},
-    utils::{with_timeout, log_debug, log_success, log_error, DEFAULT_REQUEST_TIMEOUT},
+    utils::{with_timeout, log_debug, log_success, log_error, spawn_stream_task, DEFAULT_REQUEST_TIMEOUT},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct McpServiceHandler {
     client: Option<McpServiceClient<Channel>>,
 }
@@ -38,9 +40,7 @@ impl McpServiceHandler {
             log_success(&format!("Received MCP servers from cline-core, count: {}", 
                 mcp_servers.mcp_servers.len()));
             
-            // 手动构造响应
-            let servers_value = self.build_mcp_servers_response(&mcp_servers);
-            Ok(servers_value)
+            Ok(to_json(&mcp_servers)?)
         } else {
             Err("No McpService gRPC client available".into())
         }
@@ -72,9 +72,7 @@ impl McpServiceHandler {
                     if let Some(config) = stream_config {
                         if config.enable_streaming {
                             log_debug("[McpService] Starting background stream processing with config");
-                            tokio::spawn(async move {
-                                let _ = Self::handle_background_mcp_servers_stream(stream, config).await;
-                            });
+                            spawn_stream_task(config.clone(), Self::handle_background_mcp_servers_stream(stream, config));
                         } else {
                             log_debug("[McpService] Starting default background stream processing");
                             // 即使没有启用显式流式处理，也要保持连接以接收 McpHub 的状态推送
@@ -127,7 +125,7 @@ impl McpServiceHandler {
         let mut last_servers: Option<Value> = None;
         
         while let Some(servers_result) = stream.message().await? {
-            let servers_value = self.build_mcp_servers_response(&servers_result);
+            let servers_value = to_json(&servers_result)?;
             
             // 如果有回调，调用它
             if let Some(ref callback) = config.callback {
@@ -157,86 +155,6 @@ impl McpServiceHandler {
         })))
     }
     
-    // 辅助方法：构造 MCP 服务器响应
-    fn build_mcp_servers_response(&self, mcp_servers: &crate::grpc_client::cline::McpServers) -> Value {
-        serde_json::json!({
-            "mcp_servers": mcp_servers.mcp_servers.iter().map(|server| {
-                serde_json::json!({
-                    "name": server.name,
-                    "config": server.config,
-                    "status": server.status as i32,
-                    "error": server.error,
-                    "disabled": server.disabled,
-                    "timeout": server.timeout,
-                    "tools": server.tools.iter().map(|tool| {
-                        serde_json::json!({
-                            "name": tool.name,
-                            "description": tool.description,
-                            "input_schema": tool.input_schema,
-                            "auto_approve": tool.auto_approve
-                        })
-                    }).collect::<Vec<_>>(),
-                    "resources": server.resources.iter().map(|resource| {
-                        serde_json::json!({
-                            "uri": resource.uri,
-                            "name": resource.name,
-                            "mime_type": resource.mime_type,
-                            "description": resource.description
-                        })
-                    }).collect::<Vec<_>>(),
-                    "resource_templates": server.resource_templates.iter().map(|template| {
-                        serde_json::json!({
-                            "uri_template": template.uri_template,
-                            "name": template.name,
-                            "mime_type": template.mime_type,
-                            "description": template.description
-                        })
-                    }).collect::<Vec<_>>()
-                })
-            }).collect::<Vec<_>>()
-        })
-    }
-    
-    // 静态方法：构造 MCP 服务器响应（用于后台处理）
-    fn build_static_mcp_servers_response(mcp_servers: &crate::grpc_client::cline::McpServers) -> Value {
-        serde_json::json!({
-            "mcp_servers": mcp_servers.mcp_servers.iter().map(|server| {
-                serde_json::json!({
-                    "name": server.name,
-                    "config": server.config,
-                    "status": server.status as i32,
-                    "error": server.error,
-                    "disabled": server.disabled,
-                    "timeout": server.timeout,
-                    "tools": server.tools.iter().map(|tool| {
-                        serde_json::json!({
-                            "name": tool.name,
-                            "description": tool.description,
-                            "input_schema": tool.input_schema,
-                            "auto_approve": tool.auto_approve
-                        })
-                    }).collect::<Vec<_>>(),
-                    "resources": server.resources.iter().map(|resource| {
-                        serde_json::json!({
-                            "uri": resource.uri,
-                            "name": resource.name,
-                            "mime_type": resource.mime_type,
-                            "description": resource.description
-                        })
-                    }).collect::<Vec<_>>(),
-                    "resource_templates": server.resource_templates.iter().map(|template| {
-                        serde_json::json!({
-                            "uri_template": template.uri_template,
-                            "name": template.name,
-                            "mime_type": template.mime_type,
-                            "description": template.description
-                        })
-                    }).collect::<Vec<_>>()
-                })
-            }).collect::<Vec<_>>()
-        })
-    }
-    
     // 静态方法：在后台处理 MCP 服务器流式数据
     async fn handle_background_mcp_servers_stream(
         mut stream: tonic::Streaming<crate::grpc_client::cline::McpServers>,
@@ -250,14 +168,10 @@ impl McpServiceHandler {
         while let Some(servers_result) = stream.message().await.map_err(|e| {
             format!("Stream error: {}", e)
         })? {
-            let servers_value = Self::build_static_mcp_servers_response(&servers_result);
+            let servers_value = to_json(&servers_result)?;
             
-            // 如果有回调，调用它
-            if let Some(ref callback) = config.callback {
-                if let Err(e) = callback(servers_value) {
-                    log_debug(&format!("Background stream callback error: {}", e));
-                }
-            }
+            // 转发给调用方
+            config.send(servers_value).await;
             
             message_count += 1;
             log_debug(&format!("Processed background MCP servers message {}/{}", message_count, max_messages));
@@ -288,9 +202,6 @@ impl McpServiceHandler {
         })? {
             message_count += 1;
             
-            // 构建服务器状态响应
-            let servers_value = Self::build_static_mcp_servers_response(&servers_result);
-            
             log_debug(&format!(
                 "[McpService] Received McpHub status update #{}: {} servers", 
                 message_count,
@@ -330,10 +241,7 @@ impl McpServiceHandler {
             "subscribeToMcpServers" => self.subscribe_to_mcp_servers_with_config(stream_config).await,
             _ => {
                 log_debug(&format!("McpService method not implemented: {}", method));
-                Ok(serde_json::json!({
-                    "success": true,
-                    "message": format!("McpService method {} not implemented yet", method)
-                }))
+                Err(UnhandledMethod::new("McpService", method).into())
             }
         }
     }
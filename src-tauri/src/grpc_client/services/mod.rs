@@ -1,15 +1,47 @@
+// 请求按 proto3 JSON 解析为 RPC 的请求类型（如 baseUrl、apiKey 原样传递），响应映射回 JSON
+// client 为 Result，只有匹配到已知方法时才检查连接，未知方法仍返回 not-implemented
+macro_rules! forward_unary {
+    ($client:expr, $rpc:ident, $method:expr, $message:expr) => {{
+        let client = $client?;
+        let request = tonic::Request::new($crate::grpc_client::reflection::from_json($message)?);
+        let response = $crate::grpc_client::utils::with_timeout(
+            client.$rpc(request),
+            $crate::grpc_client::utils::DEFAULT_REQUEST_TIMEOUT,
+            $method,
+        ).await?;
+        $crate::grpc_client::utils::log_success(&format!("Received {} response from cline-core", $method));
+        Ok($crate::grpc_client::reflection::to_json(&response.into_inner())?)
+    }};
+}
+
 pub mod state_service;
 pub mod ui_service;
 pub mod mcp_service;
-pub mod account_service;
 pub mod models_service;
+pub mod task_service;
+pub mod file_service;
+pub mod checkpoints_service;
+pub mod browser_service;
 
 #[cfg(test)]
 mod tests_ui_service;
+#[cfg(test)]
+mod tests_models_service;
+#[cfg(test)]
+mod tests_task_service;
+#[cfg(test)]
+mod tests_file_service;
+#[cfg(test)]
+mod tests_checkpoints_service;
+#[cfg(test)]
+mod tests_browser_service;
 
 // 重新导出服务处理器
 pub use state_service::StateServiceHandler;
 pub use ui_service::UiServiceHandler;
 pub use mcp_service::McpServiceHandler;
-pub use account_service::AccountServiceHandler;
-pub use models_service::ModelsServiceHandler;
\ No newline at end of file
+pub use models_service::ModelsServiceHandler;
+pub use task_service::TaskServiceHandler;
+pub use file_service::{DesktopFileOps, FileServiceHandler};
+pub use checkpoints_service::CheckpointsServiceHandler;
+pub use browser_service::BrowserServiceHandler;
\ No newline at end of file
@@ -1,20 +1,35 @@
+use prost::{Message, Name};
+use prost_reflect::{EnumDescriptor, Kind};
 use serde_json::Value;
 use tonic::transport::Channel;
 use tonic::Request;
 use crate::grpc_client::{
-    cline::{models_service_client::ModelsServiceClient, UpdateApiConfigurationRequest, ModelsApiConfiguration, Metadata},
+    cline::{models_service_client::ModelsServiceClient, UpdateApiConfigurationRequest, ModelsApiConfiguration, Metadata, StringArray},
+    error::{GrpcError, UnhandledMethod},
+    local_models::{merge_model_lists, LocalModelDiscovery},
+    reflection::{from_json, message_descriptor, to_json},
     types::{GrpcResult, StreamConfig},
-    utils::{log_debug, log_success, log_error, with_timeout, DEFAULT_REQUEST_TIMEOUT},
+    utils::{log_debug, log_success, with_timeout, spawn_stream_task, DEFAULT_REQUEST_TIMEOUT},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ModelsServiceHandler {
     client: Option<ModelsServiceClient<Channel>>,
+    local_models: LocalModelDiscovery,
 }
 
 impl ModelsServiceHandler {
     pub fn new() -> Self {
-        Self { client: None }
+        Self::with_local_models(LocalModelDiscovery::default())
+    }
+
+    pub fn with_local_models(local_models: LocalModelDiscovery) -> Self {
+        Self { client: None, local_models }
+    }
+
+    // 这些方法可以只依靠本地发现返回结果
+    pub fn is_local_models_method(method: &str) -> bool {
+        matches!(method, "getOllamaModels" | "getLmStudioModels")
     }
 
     pub fn set_client(&mut self, channel: Channel) {
@@ -26,7 +41,7 @@ impl ModelsServiceHandler {
             log_debug("Calling updateApiConfigurationProto on cline-core");
             
             // 先解析 JSON 消息到 protobuf 结构（在借用之前）
-            let api_config = Self::parse_api_configuration_from_json_static(message)?;
+            let api_config = Self::parse_update_request(message)?;
             
             let request = Request::new(UpdateApiConfigurationRequest {
                 metadata: Some(Metadata {}),
@@ -39,169 +54,209 @@ impl ModelsServiceHandler {
                 "updateApiConfigurationProto"
             ).await?;
             
-            let _result = response.into_inner();
             log_success("API configuration updated successfully in cline-core");
-            
-            Ok(serde_json::json!({
-                "success": true
-            }))
+            Ok(to_json(&response.into_inner())?)
         } else {
             Err("No ModelsService gRPC client available".into())
         }
     }
     
-    fn parse_api_configuration_from_json_static(message: &Value) -> GrpcResult<ModelsApiConfiguration> {
-        log_debug(&format!("Parsing API configuration from JSON: {}", message));
+    // 前端发送完整的 UpdateApiConfigurationRequest，也兼容直接发送 ApiConfiguration
+    pub fn parse_update_request(message: &Value) -> Result<ModelsApiConfiguration, GrpcError> {
+        let configuration = message
+            .get("apiConfiguration")
+            .or_else(|| message.get("api_configuration"))
+            .unwrap_or(message);
+        Self::parse_api_configuration(configuration)
+    }
+    
+    // 所有字段按 proto3 JSON 规则解析，provider 等枚举字段先做名称规范化
+    pub fn parse_api_configuration(configuration: &Value) -> Result<ModelsApiConfiguration, GrpcError> {
+        log_debug(&format!("Parsing API configuration from JSON: {}", configuration));
         
-        // 从 JSON 中提取字段并转换为枚举值
-        let plan_mode_api_provider = message.get("planModeApiProvider")
-            .and_then(|v| v.as_str())
-            .map(|s| Self::string_to_api_provider_static(s));
-            
-        let act_mode_api_provider = message.get("actModeApiProvider")
-            .and_then(|v| v.as_str())
-            .map(|s| Self::string_to_api_provider_static(s));
+        let mut configuration = configuration.clone();
+        if let Some(fields) = configuration.as_object_mut() {
+            let descriptor = message_descriptor(&ModelsApiConfiguration::full_name())?;
+            for field in descriptor.fields() {
+                let enum_descriptor = match field.kind() {
+                    Kind::Enum(enum_descriptor) => enum_descriptor,
+                    _ => continue,
+                };
+                for key in [field.json_name(), field.name()] {
+                    if let Some(value) = fields.get_mut(key) {
+                        Self::normalize_enum_value(&enum_descriptor, key, value)?;
+                    }
+                }
+            }
+        }
         
+        let api_config: ModelsApiConfiguration = from_json(&configuration)?;
         log_debug(&format!("Parsed API providers - plan: {:?}, act: {:?}", 
-            plan_mode_api_provider, act_mode_api_provider));
+            api_config.plan_mode_api_provider, api_config.act_mode_api_provider));
+        Ok(api_config)
+    }
+    
+    // 接受枚举名（ANTHROPIC）、前端的 provider id（openai-native）和数值，未知值报错而不是退回默认 provider
+    fn normalize_enum_value(enum_descriptor: &EnumDescriptor, field: &str, value: &mut Value) -> Result<(), GrpcError> {
+        let resolved = match value {
+            Value::Null => return Ok(()),
+            Value::String(name) => enum_descriptor.get_value_by_name(&name.to_uppercase().replace('-', "_")),
+            Value::Number(number) => number
+                .as_i64()
+                .and_then(|number| i32::try_from(number).ok())
+                .and_then(|number| enum_descriptor.get_value(number)),
+            _ => None,
+        };
         
-        // 创建一个基本的 ModelsApiConfiguration，只设置必要的字段
-        Ok(ModelsApiConfiguration {
-            plan_mode_api_provider,
-            act_mode_api_provider,
-            ..Default::default()
-        })
+        match resolved {
+            Some(enum_value) => {
+                *value = Value::String(enum_value.name().to_string());
+                Ok(())
+            }
+            None => Err(GrpcError::invalid_argument(format!(
+                "Unknown {} value for {}: {}", enum_descriptor.name(), field, value
+            ))),
+        }
+    }
+
+    // 同时查询 cline-core 和本机的 Ollama / LM Studio，合并两边的模型列表
+    async fn get_local_models(&mut self, method: &str, message: &Value) -> GrpcResult<Value> {
+        let base_url = message.get("value").and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+        let is_ollama = method == "getOllamaModels";
+        
+        let local_models = &self.local_models;
+        let local = async {
+            if is_ollama {
+                local_models.ollama_models(base_url).await
+            } else {
+                local_models.lm_studio_models(base_url).await
+            }
+        };
+        let core = Self::get_core_models(self.client.clone(), is_ollama, message);
+        let (core, local) = tokio::join!(core, local);
+        
+        let values = match (core, local) {
+            (Ok(core), Ok(local)) => merge_model_lists(core, local),
+            (Ok(core), Err(e)) => {
+                log_debug(&format!("[ModelsService] Local discovery for {} failed: {}", method, e));
+                core
+            }
+            (Err(e), Ok(local)) => {
+                log_debug(&format!("[ModelsService] {} from cline-core failed, using local discovery: {}", method, e));
+                local
+            }
+            (Err(e), Err(_)) => return Err(e),
+        };
+        
+        log_success(&format!("[ModelsService] {} returned {} models", method, values.len()));
+        Ok(to_json(&StringArray { values })?)
+    }
+    
+    async fn get_core_models(
+        client: Option<ModelsServiceClient<Channel>>,
+        is_ollama: bool,
+        message: &Value,
+    ) -> GrpcResult<Vec<String>> {
+        let mut client = client.ok_or("No ModelsService gRPC client available")?;
+        let request = Request::new(from_json(message)?);
+        let response = if is_ollama {
+            with_timeout(client.get_ollama_models(request), DEFAULT_REQUEST_TIMEOUT, "getOllamaModels").await?
+        } else {
+            with_timeout(client.get_lm_studio_models(request), DEFAULT_REQUEST_TIMEOUT, "getLmStudioModels").await?
+        };
+        Ok(response.into_inner().values)
     }
     
-    fn string_to_api_provider_static(provider: &str) -> i32 {
-        match provider.to_lowercase().as_str() {
-            "anthropic" => 0,
-            "openrouter" => 1,
-            "bedrock" => 2,
-            "vertex" => 3,
-            "openai" => 4,
-            "ollama" => 5,
-            "lmstudio" => 6,
-            "gemini" => 7,
-            "openai_native" => 8,
-            "requesty" => 9,
-            "together" => 10,
-            "deepseek" => 11,
-            "qwen" => 12,
-            "doubao" => 13,
-            "mistral" => 14,
-            "vscode_lm" => 15,
-            "cline" => 16,
-            "litellm" => 17,
-            "nebius" => 18,
-            "fireworks" => 19,
-            "asksage" => 20,
-            "xai" => 21,
-            "sambanova" => 22,
-            "cerebras" => 23,
-            "groq" => 24,
-            "sapaicore" => 25,
-            "claude_code" => 26,
-            "moonshot" => 27,
-            "huggingface" => 28,
-            "huawei_cloud_maas" => 29,
-            "baseten" => 30,
-            "zai" => 31,
-            "vercel_ai_gateway" => 32,
-            _ => 0, // 默认为 ANTHROPIC
+    // 订阅 OpenRouter 模型列表更新
+    async fn subscribe_to_open_router_models(&mut self, message: &Value, stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
+        let client = self.client.as_mut().ok_or("No ModelsService gRPC client available")?;
+        log_debug("Calling subscribeToOpenRouterModels on cline-core");
+        
+        let request = Request::new(from_json(message)?);
+        let mut stream = client
+            .subscribe_to_open_router_models(request)
+            .await
+            .map_err(|status| GrpcError::from_status(&status, "subscribeToOpenRouterModels"))?
+            .into_inner();
+        log_success("[ModelsService] Established subscribeToOpenRouterModels stream");
+        
+        match stream_config {
+            // 流式请求在后台转发每次刷新的模型列表
+            Some(config) if config.enable_streaming => {
+                spawn_stream_task(config.clone(), Self::handle_background_models_stream(stream, config));
+                Ok(serde_json::json!({}))
+            }
+            // 非流式调用只返回第一条消息
+            _ => match stream.message().await {
+                Ok(Some(first)) => Ok(to_json(&first)?),
+                Ok(None) => Ok(serde_json::json!({})),
+                Err(status) => Err(GrpcError::from_status(&status, "subscribeToOpenRouterModels").into()),
+            },
         }
     }
+    
+    // 静态方法：在后台处理模型列表流式数据
+    async fn handle_background_models_stream<T: Message + Name>(
+        mut stream: tonic::Streaming<T>,
+        config: StreamConfig,
+    ) -> GrpcResult<()> {
+        let max_messages = config.max_messages.unwrap_or(usize::MAX);
+        let mut message_count = 0;
+        
+        while let Some(models_result) = stream
+            .message()
+            .await
+            .map_err(|status| GrpcError::from_status(&status, "subscribeToOpenRouterModels"))?
+        {
+            config.send(to_json(&models_result)?).await;
+            message_count += 1;
+            if message_count >= max_messages {
+                break;
+            }
+        }
+        
+        log_success(&format!("[ModelsService] OpenRouter models stream completed, forwarded {} messages", message_count));
+        Ok(())
+    }
 
-    pub async fn handle_request(&mut self, method: &str, _message: &Value) -> GrpcResult<Value> {
-        self.handle_request_with_config(method, _message, None).await
+    pub async fn handle_request(&mut self, method: &str, message: &Value) -> GrpcResult<Value> {
+        self.handle_request_with_config(method, message, None).await
     }
 
     pub async fn handle_request_with_config(
         &mut self, 
         method: &str, 
-        _message: &Value, 
-        _stream_config: Option<StreamConfig>
+        message: &Value, 
+        stream_config: Option<StreamConfig>
     ) -> GrpcResult<Value> {
+        log_debug(&format!("Processing ModelsService.{} request", method));
+        
+        if method == "updateApiConfigurationProto" {
+            return self.update_api_configuration_proto(message).await;
+        }
+        if Self::is_local_models_method(method) {
+            return self.get_local_models(method, message).await;
+        }
+        if method == "subscribeToOpenRouterModels" {
+            return self.subscribe_to_open_router_models(message, stream_config).await;
+        }
+        
+        let client = self.client.as_mut().ok_or("No ModelsService gRPC client available");
         match method {
-            "updateApiConfigurationProto" => {
-                log_debug("Processing updateApiConfigurationProto request");
-                self.update_api_configuration_proto(_message).await
-            }
-            "subscribeToOpenRouterModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "lastUpdated": chrono::Utc::now().timestamp_millis()
-                }))
-            }
-            "getOllamaModels" => {
-                Ok(serde_json::json!({
-                    "models": []
-                }))
-            }
-            "getLmStudioModels" => {
-                Ok(serde_json::json!({
-                    "models": []
-                }))
-            }
-            "getVsCodeLmModels" => {
-                Ok(serde_json::json!({
-                    "models": []
-                }))
-            }
-            "refreshOpenRouterModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "refreshHuggingFaceModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "refreshOpenAiModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "refreshVercelAiGatewayModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "refreshRequestyModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "refreshGroqModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "refreshBasetenModels" => {
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
-            }
-            "getSapAiCoreModels" => {
-                Ok(serde_json::json!({
-                    "models": []
-                }))
-            }
+            "getVsCodeLmModels" => forward_unary!(client, get_vs_code_lm_models, method, message),
+            "refreshOpenRouterModels" => forward_unary!(client, refresh_open_router_models, method, message),
+            "refreshHuggingFaceModels" => forward_unary!(client, refresh_hugging_face_models, method, message),
+            "refreshOpenAiModels" => forward_unary!(client, refresh_open_ai_models, method, message),
+            "refreshVercelAiGatewayModels" => forward_unary!(client, refresh_vercel_ai_gateway_models, method, message),
+            "refreshRequestyModels" => forward_unary!(client, refresh_requesty_models, method, message),
+            "refreshGroqModels" => forward_unary!(client, refresh_groq_models, method, message),
+            "refreshBasetenModels" => forward_unary!(client, refresh_baseten_models, method, message),
+            "getSapAiCoreModels" => forward_unary!(client, get_sap_ai_core_models, method, message),
+            // 其余方法由 ClineGrpcClient 通过描述符动态转发
             _ => {
                 log_debug(&format!("ModelsService method not implemented: {}", method));
-                Ok(serde_json::json!({
-                    "models": [],
-                    "success": true
-                }))
+                Err(UnhandledMethod::new("ModelsService", method).into())
             }
         }
     }
-}
\ No newline at end of file
+}
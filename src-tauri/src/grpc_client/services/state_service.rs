@@ -4,11 +4,13 @@ use serde_json::Value;
 
 use crate::grpc_client::{
     cline::{state_service_client::StateServiceClient, EmptyRequest, Metadata},
+    error::UnhandledMethod,
+    reflection::to_json,
     types::{GrpcResult, StreamConfig},
-    utils::{with_timeout, log_debug, log_success, DEFAULT_REQUEST_TIMEOUT},
+    utils::{with_timeout, log_debug, log_success, spawn_stream_task, DEFAULT_REQUEST_TIMEOUT},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StateServiceHandler {
     client: Option<StateServiceClient<Channel>>,
 }
@@ -40,10 +42,8 @@ impl StateServiceHandler {
             log_success(&format!("Received state from cline-core, state_json length: {}", 
                 state.state_json.len()));
             
-            // 返回正确的 State 消息结构，保持 stateJson 字段
-            let state_response = serde_json::json!({
-                "stateJson": state.state_json
-            });
+            // 返回 State 消息的 proto3 JSON 格式
+            let state_response = to_json(&state)?;
             
             println!("[DEBUG] ===== RETURNING STATE RESPONSE TO FRONTEND =====");
             println!("[DEBUG] State response structure: {}", 
@@ -72,7 +72,7 @@ impl StateServiceHandler {
                 metadata: Some(Metadata {}),
             });
             
-            println!("[DEBUG] Sending gRPC request to cline-core on port 26040");
+            println!("[DEBUG] Sending gRPC request to cline-core");
             match client.subscribe_to_state(request).await {
                 Ok(stream_result) => {
                     let mut stream = stream_result.into_inner();
@@ -90,16 +90,24 @@ impl StateServiceHandler {
                                 &state_result.state_json 
                             });
                         
-                        // 在后台继续处理流以接收后续状态更新
-                        println!("[DEBUG] Starting background stream processing for subsequent updates");
-                        tokio::spawn(async move {
-                            let _ = Self::handle_default_state_stream(stream).await;
-                        });
-                        
                         // 返回初始状态给前端
-                        let state_response = serde_json::json!({
-                            "stateJson": state_result.state_json
-                        });
+                        let state_response = to_json(&state_result)?;
+                        
+                        // 在后台继续处理流以接收后续状态更新
+                        match stream_config {
+                            Some(config) if config.enable_streaming => {
+                                println!("[DEBUG] Starting background stream processing with config for subsequent updates");
+                                // 流式请求的每条状态（包括初始状态）都通过回调转发给前端
+                                config.send(state_response.clone()).await;
+                                spawn_stream_task(config.clone(), Self::handle_background_state_stream(stream, config));
+                            }
+                            _ => {
+                                println!("[DEBUG] Starting background stream processing for subsequent updates");
+                                tokio::spawn(async move {
+                                    let _ = Self::handle_default_state_stream(stream).await;
+                                });
+                            }
+                        }
                         
                         println!("[DEBUG] ===== RETURNING INITIAL STATE RESPONSE TO FRONTEND =====");
                         println!("[DEBUG] State response structure: {}", 
@@ -136,11 +144,7 @@ impl StateServiceHandler {
         let mut last_state: Option<Value> = None;
         
         while let Some(state_result) = stream.message().await? {
-            let state_value: Value = serde_json::from_str(&state_result.state_json)
-                .unwrap_or_else(|e| {
-                    log_debug(&format!("Failed to parse state_json: {}, using raw string", e));
-                    serde_json::json!({ "state_json": state_result.state_json })
-                });
+            let state_value = to_json(&state_result)?;
             
             // 如果有回调，调用它
             if let Some(ref callback) = config.callback {
@@ -196,16 +200,10 @@ impl StateServiceHandler {
                 });
             
             // 构建状态值
-            let state_value = serde_json::json!({
-                "stateJson": state_result.state_json
-            });
+            let state_value = to_json(&state_result)?;
             
-            // 如果有回调，调用它来转发状态更新到前端
-            if let Some(ref callback) = config.callback {
-                if let Err(e) = callback(state_value) {
-                    println!("[DEBUG] Background state stream callback error: {}", e);
-                }
-            }
+            // 通过回调转发状态更新到前端
+            config.send(state_value).await;
             
             println!("[DEBUG] Processed background state update {}/{}", message_count, max_messages);
             
@@ -288,10 +286,7 @@ impl StateServiceHandler {
             "subscribeToState" => self.subscribe_to_state_with_config(stream_config).await,
             _ => {
                 log_debug(&format!("StateService method not implemented: {}", method));
-                Ok(serde_json::json!({
-                    "success": true,
-                    "message": format!("StateService method {} not implemented yet", method)
-                }))
+                Err(UnhandledMethod::new("StateService", method).into())
             }
         }
     }
@@ -0,0 +1,82 @@
+use serde_json::Value;
+use tonic::transport::Channel;
+use crate::grpc_client::{
+    cline::task_service_client::TaskServiceClient,
+    error::{GrpcError, UnhandledMethod},
+    types::{GrpcResult, StreamConfig},
+    utils::log_debug,
+};
+
+#[derive(Debug, Clone)]
+pub struct TaskServiceHandler {
+    client: Option<TaskServiceClient<Channel>>,
+}
+
+impl TaskServiceHandler {
+    pub fn new() -> Self {
+        Self { client: None }
+    }
+
+    pub fn set_client(&mut self, channel: Channel) {
+        self.client = Some(TaskServiceClient::new(channel));
+    }
+
+    // 在转发前拒绝 cline-core 会静默忽略或按空值处理的请求
+    fn validate_request(method: &str, message: &Value) -> Result<(), GrpcError> {
+        let non_empty = |field: &str| message[field].as_str().map_or(false, |v| !v.is_empty());
+        let has_items = |field: &str| message[field].as_array().map_or(false, |v| !v.is_empty());
+
+        let expected = match method {
+            // 新任务至少要有文字、图片或文件之一
+            "newTask" if !(non_empty("text") || has_items("images") || has_items("files")) => "text, images or files",
+            "askResponse" if !non_empty("responseType") => "a responseType",
+            // showTaskWithId / exportTaskWithId 的 StringRequest 必须带任务 id
+            "showTaskWithId" | "exportTaskWithId" if !non_empty("value") => "a task id in \"value\"",
+            "deleteTasksWithIds" if !has_items("value")
+                || !message["value"].as_array().into_iter().flatten().all(|id| id.as_str().map_or(false, |id| !id.is_empty())) =>
+            {
+                "a non-empty list of task ids in \"value\""
+            }
+            "toggleTaskFavorite" if !non_empty("taskId") => "a taskId",
+            _ => return Ok(()),
+        };
+        Err(GrpcError::invalid_argument(format!("TaskService.{} requires {}", method, expected)))
+    }
+
+    pub async fn handle_request(&mut self, method: &str, message: &Value) -> GrpcResult<Value> {
+        self.handle_request_with_config(method, message, None).await
+    }
+
+    pub async fn handle_request_with_config(
+        &mut self,
+        method: &str,
+        message: &Value,
+        _stream_config: Option<StreamConfig>
+    ) -> GrpcResult<Value> {
+        log_debug(&format!("Processing TaskService.{} request", method));
+
+        Self::validate_request(method, message)?;
+
+        let client = self.client.as_mut().ok_or("No TaskService gRPC client available");
+        match method {
+            // 任务生命周期
+            "newTask" => forward_unary!(client, new_task, method, message),
+            "cancelTask" => forward_unary!(client, cancel_task, method, message),
+            "clearTask" => forward_unary!(client, clear_task, method, message),
+            "askResponse" => forward_unary!(client, ask_response, method, message),
+            // 按 id 操作历史任务
+            "showTaskWithId" => forward_unary!(client, show_task_with_id, method, message),
+            "deleteTasksWithIds" => forward_unary!(client, delete_tasks_with_ids, method, message),
+            "exportTaskWithId" => forward_unary!(client, export_task_with_id, method, message),
+            // 任务历史和收藏
+            "getTaskHistory" => forward_unary!(client, get_task_history, method, message),
+            "getTotalTasksSize" => forward_unary!(client, get_total_tasks_size, method, message),
+            "deleteAllTaskHistory" => forward_unary!(client, delete_all_task_history, method, message),
+            "toggleTaskFavorite" => forward_unary!(client, toggle_task_favorite, method, message),
+            _ => {
+                log_debug(&format!("TaskService method not implemented: {}", method));
+                Err(UnhandledMethod::new("TaskService", method).into())
+            }
+        }
+    }
+}
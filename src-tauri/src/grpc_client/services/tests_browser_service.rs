@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod browser_service_tests {
+    use crate::grpc_client::{
+        error::{ErrorCode, GrpcError},
+        services::BrowserServiceHandler,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_connection_is_tested_locally() {
+        let mut handler = BrowserServiceHandler::new();
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+
+        // 不需要 cline-core，失败时返回 success: false 而不是错误
+        let response = handler.handle_request("testBrowserConnection", &json!({ "value": closed })).await.unwrap();
+        assert_eq!(response["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_forwarded_when_connected() {
+        let mut handler = BrowserServiceHandler::new();
+        handler.set_client(tonic::transport::Channel::from_static("http://127.0.0.1:1").connect_lazy());
+
+        // 已连接时由 cline-core 探测，本机探测只是后备
+        let result = handler.handle_request("getDetectedChromePath", &json!({})).await;
+        let error = GrpcError::classify(result.unwrap_err().as_ref());
+        assert_eq!(error.code, ErrorCode::Unavailable);
+
+        // 其余方法交给 ClineGrpcClient 动态转发
+        for method in ["testBrowserConnection", "discoverBrowser", "getBrowserConnectionInfo", "updateBrowserSettings"] {
+            let result = handler.handle_request(method, &json!({})).await;
+            let error = GrpcError::classify(result.unwrap_err().as_ref());
+            assert_eq!(error.code, ErrorCode::NotImplemented, "{}", method);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod checkpoints_service_tests {
+    use crate::grpc_client::{
+        error::{ErrorCode, GrpcError},
+        services::{checkpoints_service::checkpoints_from_state, CheckpointsServiceHandler},
+    };
+    use serde_json::{json, Value};
+
+    async fn request_error(method: &str, message: Value) -> GrpcError {
+        let mut handler = CheckpointsServiceHandler::new();
+        let result = handler.handle_request(method, &message).await;
+        GrpcError::classify(result.unwrap_err().as_ref())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_requests_are_rejected() {
+        for (method, message) in [
+            ("checkpointDiff", json!({})),
+            ("checkpointDiff", json!({ "value": "latest" })),
+            ("checkpointRestore", json!({ "restoreType": "task" })),
+            ("checkpointRestore", json!({ "number": 1712345678901i64, "restoreType": "everything" })),
+            ("checkpointRestore", json!({ "number": "1712345678901" })),
+        ] {
+            let error = request_error(method, message.clone()).await;
+            assert_eq!(error.code, ErrorCode::InvalidArgument, "{} {}", method, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_requests_are_forwarded() {
+        // int64 既可以是数字也可以是 proto3 JSON 的字符串形式
+        for (method, message) in [
+            ("checkpointDiff", json!({ "value": 1712345678901i64 })),
+            ("checkpointRestore", json!({ "number": "1712345678901", "restoreType": "taskAndWorkspace" })),
+        ] {
+            let mut handler = CheckpointsServiceHandler::new();
+            let result = handler.handle_request(method, &message).await;
+            assert!(result.unwrap_err().to_string().contains("No CheckpointsService gRPC client"), "{}", method);
+        }
+
+        let error = request_error("noSuchMethod", json!({})).await;
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+    }
+
+    #[test]
+    fn test_checkpoints_from_state() {
+        let state = json!({
+            "currentTaskItem": { "id": "task-1" },
+            "clineMessages": [
+                { "ts": 100, "type": "say", "say": "text", "text": "hello" },
+                { "ts": 200, "type": "say", "say": "checkpoint_created", "lastCheckpointHash": "abc123", "conversationHistoryIndex": 1 },
+                { "ts": 300, "type": "say", "say": "checkpoint_created", "lastCheckpointHash": "", "conversationHistoryIndex": 2 },
+                { "ts": 400, "type": "say", "say": "checkpoint_created", "lastCheckpointHash": "def456", "isCheckpointCheckedOut": true, "conversationHistoryIndex": 3 }
+            ]
+        });
+
+        let result = checkpoints_from_state(&state.to_string()).unwrap();
+        assert_eq!(result["taskId"], "task-1");
+        let checkpoints = result["checkpoints"].as_array().unwrap();
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[0]["ts"], 200);
+        assert_eq!(checkpoints[0]["hash"], "abc123");
+        assert_eq!(checkpoints[0]["isCheckpointCheckedOut"], false);
+        assert_eq!(checkpoints[1]["hash"], "def456");
+        assert_eq!(checkpoints[1]["isCheckpointCheckedOut"], true);
+
+        // 没有任务时返回空列表
+        let empty = checkpoints_from_state("{}").unwrap();
+        assert_eq!(empty["taskId"], Value::Null);
+        assert!(empty["checkpoints"].as_array().unwrap().is_empty());
+
+        let error = checkpoints_from_state("not json").unwrap_err();
+        assert_eq!(error.code, ErrorCode::Internal);
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod file_service_tests {
+    use crate::grpc_client::{
+        error::{ErrorCode, GrpcError},
+        services::{
+            file_service::{image_data_url, relative_path, resolve_workspace_path, search_workspace},
+            DesktopFileOps, FileServiceHandler,
+        },
+        types::GrpcResult,
+    };
+    use serde_json::json;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    // 记录调用的桌面端实现
+    #[derive(Debug, Default)]
+    struct FakeDesktop {
+        root: Option<PathBuf>,
+        picked: Vec<PathBuf>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl DesktopFileOps for FakeDesktop {
+        fn workspace_root(&self) -> Option<PathBuf> {
+            self.root.clone()
+        }
+
+        async fn open_path(&self, path: &Path) -> GrpcResult<()> {
+            self.calls.lock().unwrap().push(format!("open_path {}", path.display()));
+            Ok(())
+        }
+
+        async fn open_url(&self, url: &str) -> GrpcResult<()> {
+            self.calls.lock().unwrap().push(format!("open_url {}", url));
+            Ok(())
+        }
+
+        async fn pick_files(&self, images_only: bool) -> GrpcResult<Vec<PathBuf>> {
+            self.calls.lock().unwrap().push(format!("pick_files {}", images_only));
+            Ok(self.picked.clone())
+        }
+
+        async fn write_clipboard(&self, text: &str) -> GrpcResult<()> {
+            self.calls.lock().unwrap().push(format!("write_clipboard {}", text));
+            Ok(())
+        }
+
+        fn is_user_selected(&self, path: &Path) -> bool {
+            self.picked.iter().any(|picked| picked.canonicalize().ok().as_deref() == Some(path))
+        }
+    }
+
+    fn temp_workspace(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("cline-desktop-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src/grpc_client")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/left-pad")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("src/grpc_client/mod.rs"), "").unwrap();
+        std::fs::write(root.join("node_modules/left-pad/main.rs"), "").unwrap();
+        std::fs::write(root.join(".git/main.rs"), "").unwrap();
+        root
+    }
+
+    #[tokio::test]
+    async fn test_desktop_operations_are_handled_natively() {
+        let root = temp_workspace("native").canonicalize().unwrap();
+        let desktop = Arc::new(FakeDesktop { root: Some(root.clone()), ..Default::default() });
+        let mut handler = FileServiceHandler::with_desktop(Some(desktop.clone()));
+
+        handler.handle_request("copyToClipboard", &json!({ "value": "hello" })).await.unwrap();
+        handler.handle_request("openMention", &json!({ "value": "/src/main.rs" })).await.unwrap();
+        handler.handle_request("openMention", &json!({ "value": "https://example.com" })).await.unwrap();
+
+        let uri = format!("file://{}", root.join("src/main.rs").display());
+        let paths = handler
+            .handle_request("getRelativePaths", &json!({ "uris": [uri, "/tmp/notes.txt"] }))
+            .await
+            .unwrap();
+        assert_eq!(paths, json!({ "paths": ["src/main.rs", "/tmp/notes.txt"] }));
+
+        assert_eq!(*desktop.calls.lock().unwrap(), vec![
+            "write_clipboard hello".to_string(),
+            format!("open_path {}", root.join("src/main.rs").display()),
+            "open_url https://example.com".to_string(),
+        ]);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_paths_outside_workspace_are_rejected() {
+        let root = temp_workspace("traversal");
+        let outside = root.with_file_name(format!("{}-outside.txt", root.file_name().unwrap().to_string_lossy()));
+        std::fs::write(&outside, "secret").unwrap();
+        let outside_name = outside.file_name().unwrap().to_string_lossy().to_string();
+        let desktop = Arc::new(FakeDesktop { root: Some(root.clone()), ..Default::default() });
+        let mut handler = FileServiceHandler::with_desktop(Some(desktop.clone()));
+
+        // .. 片段和工作区外已存在的绝对路径都不会交给系统默认程序打开
+        for (method, value) in [
+            ("openFile", format!("../{}", outside_name)),
+            ("openMention", format!("/src/../../{}", outside_name)),
+            ("openFile", outside.to_string_lossy().to_string()),
+            ("openFile", "/src/missing.rs".to_string()),
+        ] {
+            let result = handler.handle_request(method, &json!({ "value": value })).await;
+            let error = GrpcError::classify(result.unwrap_err().as_ref());
+            assert_eq!(error.code, ErrorCode::InvalidArgument, "{} {}", method, value);
+        }
+        assert!(desktop.calls.lock().unwrap().is_empty());
+
+        // 用户在对话框中选择过的文件可以打开
+        let picked = Arc::new(FakeDesktop { root: Some(root.clone()), picked: vec![outside.clone()], ..Default::default() });
+        let mut handler = FileServiceHandler::with_desktop(Some(picked.clone()));
+        handler.handle_request("openFile", &json!({ "value": outside.to_string_lossy() })).await.unwrap();
+        assert_eq!(*picked.calls.lock().unwrap(), vec![format!("open_path {}", outside.canonicalize().unwrap().display())]);
+
+        let _ = std::fs::remove_file(outside);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_search_is_forwarded_when_connected() {
+        let root = temp_workspace("connected");
+        let desktop = Arc::new(FakeDesktop { root: Some(root.clone()), ..Default::default() });
+        let mut handler = FileServiceHandler::with_desktop(Some(desktop));
+        handler.set_client(tonic::transport::Channel::from_static("http://127.0.0.1:1").connect_lazy());
+
+        // 已连接时由 cline-core 搜索，本地遍历只是未连接时的后备
+        let result = handler.handle_request("searchFiles", &json!({ "query": "main" })).await;
+        let error = GrpcError::classify(result.unwrap_err().as_ref());
+        assert_eq!(error.code, ErrorCode::Unavailable);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_other_requests_are_forwarded() {
+        let desktop = Arc::new(FakeDesktop::default());
+        let mut handler = FileServiceHandler::with_desktop(Some(desktop.clone()));
+
+        // problems 等特殊提及以及没有工作区时的搜索交给 cline-core
+        for (method, message) in [
+            ("openMention", json!({ "value": "problems" })),
+            ("searchFiles", json!({ "query": "main" })),
+            ("toggleClineRule", json!({ "rulePath": "/rules.md", "enabled": true })),
+        ] {
+            let result = handler.handle_request(method, &message).await;
+            assert!(result.unwrap_err().to_string().contains("No FileService gRPC client"), "{}", method);
+        }
+        assert!(desktop.calls.lock().unwrap().is_empty());
+
+        let result = handler.handle_request("noSuchMethod", &json!({})).await;
+        let error = GrpcError::classify(result.unwrap_err().as_ref());
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+    }
+
+    #[tokio::test]
+    async fn test_search_files_in_workspace() {
+        let root = temp_workspace("search");
+        let desktop = Arc::new(FakeDesktop { root: Some(root.clone()), ..Default::default() });
+        let mut handler = FileServiceHandler::with_desktop(Some(desktop));
+
+        let response = handler
+            .handle_request("searchFiles", &json!({ "query": "main", "mentionsRequestId": "m-1" }))
+            .await
+            .unwrap();
+        // node_modules 和隐藏目录被跳过
+        assert_eq!(response, json!({
+            "results": [{ "path": "src/main.rs", "type": "file", "label": "main.rs" }],
+            "mentionsRequestId": "m-1",
+        }));
+
+        let folders = search_workspace(&root, "grpc", Some(true), 10);
+        assert_eq!(folders, vec![("src/grpc_client".to_string(), true)]);
+        assert_eq!(search_workspace(&root, "", None, 2).len(), 2);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_select_images_returns_data_urls() {
+        let root = temp_workspace("images");
+        let image = root.join("pixel.png");
+        std::fs::write(&image, [0x89, b'P', b'N', b'G']).unwrap();
+        let desktop = Arc::new(FakeDesktop {
+            picked: vec![image.clone(), root.join("src/main.rs")],
+            ..Default::default()
+        });
+        let mut handler = FileServiceHandler::with_desktop(Some(desktop));
+
+        let response = handler.handle_request("selectFiles", &json!({ "value": true })).await.unwrap();
+        assert_eq!(response["values1"], json!(["data:image/png;base64,iVBORw=="]));
+        assert_eq!(response["values2"], json!([root.join("src/main.rs").to_string_lossy()]));
+
+        let error = image_data_url(&root.join("src/main.rs")).unwrap_err();
+        assert_eq!(GrpcError::classify(error.as_ref()).code, ErrorCode::InvalidArgument);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_workspace_paths() {
+        let root = Path::new("/home/user/project");
+
+        assert_eq!(resolve_workspace_path(Some(root), "/src/main.rs"), root.join("src/main.rs"));
+        assert_eq!(resolve_workspace_path(None, "/src/main.rs"), PathBuf::from("/src/main.rs"));
+        assert_eq!(relative_path(root, "/home/user/project/src/lib.rs"), "src/lib.rs");
+    }
+}
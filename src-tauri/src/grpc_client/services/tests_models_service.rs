@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod models_service_tests {
+    use crate::grpc_client::{
+        cline::ModelsApiConfiguration,
+        error::{ErrorCode, GrpcError},
+        reflection::{descriptor_pool, from_json, to_json},
+        services::ModelsServiceHandler,
+    };
+    use serde_json::json;
+
+    // 以前的手写映射表，前端仍可能发送这些名称
+    const LEGACY_PROVIDER_NAMES: [&str; 33] = [
+        "anthropic", "openrouter", "bedrock", "vertex", "openai", "ollama", "lmstudio", "gemini",
+        "openai_native", "requesty", "together", "deepseek", "qwen", "doubao", "mistral", "vscode_lm",
+        "cline", "litellm", "nebius", "fireworks", "asksage", "xai", "sambanova", "cerebras",
+        "groq", "sapaicore", "claude_code", "moonshot", "huggingface", "huawei_cloud_maas", "baseten",
+        "zai", "vercel_ai_gateway",
+    ];
+
+    #[test]
+    fn test_every_provider_round_trips() {
+        let providers = descriptor_pool().get_enum_by_name("cline.ApiProvider").unwrap();
+        assert!(providers.values().count() >= LEGACY_PROVIDER_NAMES.len());
+
+        for provider in providers.values() {
+            // 前端的 provider id 使用小写和连字符，如 openai-native
+            let provider_id = provider.name().to_lowercase().replace('_', "-");
+            let request = json!({
+                "apiConfiguration": {
+                    "planModeApiProvider": provider.name(),
+                    "actModeApiProvider": provider_id,
+                }
+            });
+
+            let config = ModelsServiceHandler::parse_update_request(&request).unwrap();
+            assert_eq!(config.plan_mode_api_provider, Some(provider.number()), "{}", provider.name());
+            assert_eq!(config.act_mode_api_provider, Some(provider.number()), "{}", provider_id);
+
+            let json = to_json(&config).unwrap();
+            assert_eq!(json["planModeApiProvider"], provider.name());
+            assert_eq!(json["actModeApiProvider"], provider.name());
+            assert_eq!(from_json::<ModelsApiConfiguration>(&json).unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn test_legacy_provider_names_keep_their_values() {
+        for (number, name) in LEGACY_PROVIDER_NAMES.iter().enumerate() {
+            let config = ModelsServiceHandler::parse_api_configuration(&json!({ "planModeApiProvider": name })).unwrap();
+            assert_eq!(config.plan_mode_api_provider, Some(number as i32), "{}", name);
+        }
+
+        // 也接受数值
+        let config = ModelsServiceHandler::parse_api_configuration(&json!({ "actModeApiProvider": 4 })).unwrap();
+        assert_eq!(config.act_mode_api_provider, Some(4));
+    }
+
+    #[test]
+    fn test_all_fields_are_forwarded() {
+        let request = json!({
+            "metadata": {},
+            "apiConfiguration": {
+                "apiKey": "sk-ant-test",
+                "openRouterApiKey": "sk-or-test",
+                "openAiBaseUrl": "https://api.example.com/v1",
+                "ollamaBaseUrl": "http://localhost:11434",
+                "planModeApiProvider": "OPENROUTER",
+                "planModeApiModelId": "anthropic/claude-sonnet-4",
+                "planModeThinkingBudgetTokens": 2048,
+                "actModeApiProvider": "anthropic",
+                "actModeApiModelId": "claude-sonnet-4",
+                "unknownField": true,
+            }
+        });
+
+        let config = ModelsServiceHandler::parse_update_request(&request).unwrap();
+        assert_eq!(config.api_key.as_deref(), Some("sk-ant-test"));
+        assert_eq!(config.open_router_api_key.as_deref(), Some("sk-or-test"));
+        assert_eq!(config.open_ai_base_url.as_deref(), Some("https://api.example.com/v1"));
+        assert_eq!(config.ollama_base_url.as_deref(), Some("http://localhost:11434"));
+        assert_eq!(config.plan_mode_api_model_id.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(config.act_mode_api_model_id.as_deref(), Some("claude-sonnet-4"));
+        assert!(config.plan_mode_thinking_budget_tokens.is_some());
+
+        // 直接发送配置对象时结果相同
+        let direct = ModelsServiceHandler::parse_update_request(&request["apiConfiguration"]).unwrap();
+        assert_eq!(direct, config);
+
+        let json = to_json(&config).unwrap();
+        assert_eq!(json["apiKey"], "sk-ant-test");
+        assert_eq!(json["planModeApiModelId"], "anthropic/claude-sonnet-4");
+        assert_eq!(from_json::<ModelsApiConfiguration>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_unknown_provider_is_invalid_argument() {
+        for provider in [json!("not-a-provider"), json!(9999), json!(true)] {
+            let error = ModelsServiceHandler::parse_api_configuration(&json!({ "planModeApiProvider": provider })).unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidArgument, "{}", provider);
+            assert!(error.message.contains("planModeApiProvider"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_lists_are_not_faked_without_cline_core() {
+        let mut handler = ModelsServiceHandler::new();
+
+        // 没有连接时返回错误，而不是空的 models 列表
+        for method in ["refreshGroqModels", "refreshOpenRouterModels", "refreshOpenAiModels", "subscribeToOpenRouterModels"] {
+            let result = handler.handle_request(method, &json!({ "value": "http://localhost:11434" })).await;
+            assert!(result.unwrap_err().to_string().contains("No ModelsService gRPC client"), "{}", method);
+        }
+
+        let result = handler.handle_request("noSuchMethod", &json!({})).await;
+        let error = GrpcError::classify(result.unwrap_err().as_ref());
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+    }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod task_service_tests {
+    use crate::grpc_client::{
+        error::{ErrorCode, GrpcError},
+        services::TaskServiceHandler,
+        types::ServiceHandler,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_invalid_requests_are_rejected() {
+        let mut handler = TaskServiceHandler::new();
+
+        for (method, message) in [
+            ("newTask", json!({ "text": "", "images": [], "files": [] })),
+            ("askResponse", json!({ "text": "yes" })),
+            ("showTaskWithId", json!({})),
+            ("exportTaskWithId", json!({ "value": "" })),
+            ("deleteTasksWithIds", json!({ "value": [] })),
+            ("deleteTasksWithIds", json!({ "value": ["1712345678901", ""] })),
+            ("toggleTaskFavorite", json!({ "isFavorited": true })),
+        ] {
+            let result = handler.handle_request(method, &message).await;
+            let error = GrpcError::classify(result.unwrap_err().as_ref());
+            assert_eq!(error.code, ErrorCode::InvalidArgument, "{} {}", method, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_cline_core() {
+        let mut handler = ServiceHandler::Task(TaskServiceHandler::new());
+
+        for (method, message) in [
+            ("newTask", json!({ "text": "hello", "images": [], "files": [] })),
+            ("newTask", json!({ "images": ["data:image/png;base64,AAAA"] })),
+            ("cancelTask", json!({})),
+            ("clearTask", json!({})),
+            ("askResponse", json!({ "responseType": "yesButtonClicked" })),
+            ("showTaskWithId", json!({ "value": "1712345678901" })),
+            ("deleteTasksWithIds", json!({ "value": ["1712345678901"] })),
+            ("exportTaskWithId", json!({ "value": "1712345678901" })),
+            ("getTaskHistory", json!({ "favoritesOnly": true })),
+            ("getTotalTasksSize", json!({})),
+            ("deleteAllTaskHistory", json!({})),
+            ("toggleTaskFavorite", json!({ "taskId": "1712345678901", "isFavorited": true })),
+        ] {
+            let result = handler.handle_request(method, &message).await;
+            assert!(result.unwrap_err().to_string().contains("No TaskService gRPC client"), "{}", method);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method_is_not_implemented() {
+        let mut handler = TaskServiceHandler::new();
+
+        let result = handler.handle_request("noSuchMethod", &json!({})).await;
+        let error = GrpcError::classify(result.unwrap_err().as_ref());
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+    }
+}
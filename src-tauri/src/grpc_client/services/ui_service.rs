@@ -4,11 +4,13 @@ use serde_json::Value;
 
 use crate::grpc_client::{
     cline::{ui_service_client::UiServiceClient, EmptyRequest, Metadata},
+    error::UnhandledMethod,
+    reflection::to_json,
     types::{GrpcResult, StreamConfig},
-    utils::{with_timeout, log_debug, log_success, log_error, DEFAULT_REQUEST_TIMEOUT},
+    utils::{with_timeout, log_debug, log_success, log_error, spawn_stream_task, DEFAULT_REQUEST_TIMEOUT},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UiServiceHandler {
     client: Option<UiServiceClient<Channel>>,
 }
@@ -32,31 +34,17 @@ impl UiServiceHandler {
         }
     }
     
-    pub async fn handle_request_with_config(&mut self, method: &str, message: &Value, stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
+    pub async fn handle_request_with_config(&mut self, method: &str, _message: &Value, stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
         match method {
             "subscribeToPartialMessage" => self.subscribe_to_partial_message_with_config(stream_config).await,
             "subscribeToChatButtonClicked" => self.subscribe_to_chat_button_clicked_with_config(stream_config).await,
             "initializeWebview" => self.initialize_webview().await,
             "subscribeToTheme" => self.subscribe_to_theme_with_config(stream_config).await,
             "subscribeToRelinquishControl" => self.subscribe_to_relinquish_control_with_config(stream_config).await,
-            "subscribeToFocusChatInput" => self.subscribe_to_focus_chat_input_with_config(message, stream_config).await,
-            "subscribeToAddToInput" => self.subscribe_to_add_to_input_with_config(message, stream_config).await,
-            "subscribeToMcpButtonClicked" => self.subscribe_to_mcp_button_clicked_with_config(message, stream_config).await,
-            "subscribeToHistoryButtonClicked" => self.subscribe_to_history_button_clicked_with_config(message, stream_config).await,
-            "subscribeToAccountButtonClicked" => self.subscribe_to_account_button_clicked_with_config(stream_config).await,
-            "subscribeToSettingsButtonClicked" => self.subscribe_to_settings_button_clicked_with_config(message, stream_config).await,
-            "subscribeToDidBecomeVisible" => self.subscribe_to_did_become_visible_with_config(stream_config).await,
-            "scrollToSettings" => self.scroll_to_settings(message).await,
-            "onDidShowAnnouncement" => self.on_did_show_announcement().await,
-            "getWebviewHtml" => self.get_webview_html().await,
-            "openUrl" => self.open_url(message).await,
-            "openWalkthrough" => self.open_walkthrough().await,
+            // 其余方法由 ClineGrpcClient 通过描述符动态转发
             _ => {
                 log_debug(&format!("UiService method not implemented: {}", method));
-                Ok(serde_json::json!({
-                    "success": true,
-                    "message": format!("UiService method {} not implemented yet", method)
-                }))
+                Err(UnhandledMethod::new("UiService", method).into())
             }
         }
     }
@@ -88,9 +76,7 @@ impl UiServiceHandler {
                         if config.enable_streaming {
                             // 如果明确启用了流式处理，在后台异步处理
                             log_debug("[UiService] Starting background stream processing");
-                            tokio::spawn(async move {
-                                let _ = Self::handle_background_partial_messages_stream(stream, config).await;
-                            });
+                            spawn_stream_task(config.clone(), Self::handle_background_partial_messages_stream(stream, config));
                         } else {
                             log_debug("[UiService] Starting default background stream processing");
                             // 即使没有启用显式流式处理，也要保持连接以接收部分消息推送
@@ -143,7 +129,7 @@ impl UiServiceHandler {
         let mut last_message: Option<Value> = None;
         
         while let Some(message_result) = stream.message().await? {
-            let message_value = self.build_partial_message_response(&message_result);
+            let message_value = to_json(&message_result)?;
             
             // 如果有回调，调用它
             if let Some(ref callback) = config.callback {
@@ -173,44 +159,6 @@ impl UiServiceHandler {
         })))
     }
     
-    // 辅助方法：构造部分消息响应
-    fn build_partial_message_response(&self, message_result: &crate::grpc_client::cline::ClineMessage) -> Value {
-        serde_json::json!({
-            "ts": message_result.ts,
-            "type": message_result.r#type,
-            "ask": message_result.ask,
-            "say": message_result.say,
-            "text": message_result.text,
-            "reasoning": message_result.reasoning,
-            "images": message_result.images,
-            "files": message_result.files,
-            "partial": message_result.partial,
-            "lastCheckpointHash": message_result.last_checkpoint_hash,
-            "isCheckpointCheckedOut": message_result.is_checkpoint_checked_out,
-            "isOperationOutsideWorkspace": message_result.is_operation_outside_workspace,
-            "conversationHistoryIndex": message_result.conversation_history_index
-        })
-    }
-    
-    // 静态方法：构造部分消息响应（用于后台处理）
-    fn build_static_partial_message_response(message_result: &crate::grpc_client::cline::ClineMessage) -> Value {
-        serde_json::json!({
-            "ts": message_result.ts,
-            "type": message_result.r#type,
-            "ask": message_result.ask,
-            "say": message_result.say,
-            "text": message_result.text,
-            "reasoning": message_result.reasoning,
-            "images": message_result.images,
-            "files": message_result.files,
-            "partial": message_result.partial,
-            "lastCheckpointHash": message_result.last_checkpoint_hash,
-            "isCheckpointCheckedOut": message_result.is_checkpoint_checked_out,
-            "isOperationOutsideWorkspace": message_result.is_operation_outside_workspace,
-            "conversationHistoryIndex": message_result.conversation_history_index
-        })
-    }
-    
     // 静态方法：在后台处理部分消息流式数据
     async fn handle_background_partial_messages_stream(
         mut stream: tonic::Streaming<crate::grpc_client::cline::ClineMessage>,
@@ -224,14 +172,10 @@ impl UiServiceHandler {
         while let Some(message_result) = stream.message().await.map_err(|e| {
             format!("Stream error: {}", e)
         })? {
-            let message_value = Self::build_static_partial_message_response(&message_result);
+            let message_value = to_json(&message_result)?;
             
-            // 如果有回调，调用它
-            if let Some(ref callback) = config.callback {
-                if let Err(e) = callback(message_value) {
-                    log_debug(&format!("Background stream callback error: {}", e));
-                }
-            }
+            // 转发给调用方
+            config.send(message_value).await;
             
             message_count += 1;
             log_debug(&format!("Processed background partial message {}/{}", message_count, max_messages));
@@ -262,9 +206,6 @@ impl UiServiceHandler {
         })? {
             message_count += 1;
             
-            // 构建消息响应
-            let message_value = Self::build_static_partial_message_response(&message_result);
-            
             log_debug(&format!(
                 "[UiService] Received partial message update #{}: type={}, partial={}, text_len={}", 
                 message_count,
@@ -311,10 +252,17 @@ impl UiServiceHandler {
                     let stream = stream_result.into_inner();
                     log_success("[UiService] Successfully established chat button clicked subscription");
                     
-                    // 在后台处理流
-                    tokio::spawn(async move {
-                        let _ = Self::handle_empty_stream(stream, "chat_button_clicked").await;
-                    });
+                    // 在后台处理流，流式请求时把每个事件转发给调用方
+                    match stream_config {
+                        Some(config) if config.enable_streaming => {
+                            spawn_stream_task(config.clone(), Self::handle_empty_stream(stream, "chat_button_clicked", Some(config)));
+                        }
+                        _ => {
+                            tokio::spawn(async move {
+                                let _ = Self::handle_empty_stream(stream, "chat_button_clicked", None).await;
+                            });
+                        }
+                    }
                     
                     Ok(serde_json::json!({
                         "subscription_established": true,
@@ -346,14 +294,9 @@ impl UiServiceHandler {
             });
             
             match client.initialize_webview(request).await {
-                Ok(_) => {
+                Ok(response) => {
                     log_success("[UiService] Successfully initialized webview");
-                    Ok(serde_json::json!({
-                        "success": true,
-                        "message": "Webview initialized successfully",
-                        "service": "UiService",
-                        "method": "initializeWebview"
-                    }))
+                    Ok(to_json(&response.into_inner())?)
                 }
                 Err(e) => {
                     let error_msg = format!("Failed to initialize webview: {}", e);
@@ -381,10 +324,17 @@ impl UiServiceHandler {
                     let stream = stream_result.into_inner();
                     log_success("[UiService] Successfully established theme subscription");
                     
-                    // 在后台处理流
-                    tokio::spawn(async move {
-                        let _ = Self::handle_string_stream(stream, "theme").await;
-                    });
+                    // 在后台处理流，流式请求时把每个事件转发给调用方
+                    match stream_config {
+                        Some(config) if config.enable_streaming => {
+                            spawn_stream_task(config.clone(), Self::handle_string_stream(stream, "theme", Some(config)));
+                        }
+                        _ => {
+                            tokio::spawn(async move {
+                                let _ = Self::handle_string_stream(stream, "theme", None).await;
+                            });
+                        }
+                    }
                     
                     Ok(serde_json::json!({
                         "subscription_established": true,
@@ -420,10 +370,17 @@ impl UiServiceHandler {
                     let stream = stream_result.into_inner();
                     log_success("[UiService] Successfully established relinquish control subscription");
                     
-                    // 在后台处理流
-                    tokio::spawn(async move {
-                        let _ = Self::handle_empty_stream(stream, "relinquish_control").await;
-                    });
+                    // 在后台处理流，流式请求时把每个事件转发给调用方
+                    match stream_config {
+                        Some(config) if config.enable_streaming => {
+                            spawn_stream_task(config.clone(), Self::handle_empty_stream(stream, "relinquish_control", Some(config)));
+                        }
+                        _ => {
+                            tokio::spawn(async move {
+                                let _ = Self::handle_empty_stream(stream, "relinquish_control", None).await;
+                            });
+                        }
+                    }
                     
                     Ok(serde_json::json!({
                         "subscription_established": true,
@@ -445,147 +402,26 @@ impl UiServiceHandler {
             Err(error_msg.into())
         }
     }
-    
-    async fn subscribe_to_focus_chat_input_with_config(&mut self, message: &Value, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToFocusChatInput - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to focus chat input events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToFocusChatInput"
-        }))
-    }
-    
-    async fn subscribe_to_add_to_input_with_config(&mut self, _message: &Value, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToAddToInput - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to add to input events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToAddToInput"
-        }))
-    }
-    
-    async fn subscribe_to_mcp_button_clicked_with_config(&mut self, _message: &Value, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToMcpButtonClicked - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to MCP button clicked events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToMcpButtonClicked"
-        }))
-    }
-    
-    async fn subscribe_to_history_button_clicked_with_config(&mut self, _message: &Value, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToHistoryButtonClicked - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to history button clicked events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToHistoryButtonClicked"
-        }))
-    }
-    
-    async fn subscribe_to_account_button_clicked_with_config(&mut self, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToAccountButtonClicked - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to account button clicked events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToAccountButtonClicked"
-        }))
-    }
-    
-    async fn subscribe_to_settings_button_clicked_with_config(&mut self, _message: &Value, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToSettingsButtonClicked - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to settings button clicked events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToSettingsButtonClicked"
-        }))
-    }
-    
-    async fn subscribe_to_did_become_visible_with_config(&mut self, _stream_config: Option<StreamConfig>) -> GrpcResult<Value> {
-        log_debug("[UiService] subscribeToDidBecomeVisible - returning placeholder response");
-        Ok(serde_json::json!({
-            "subscription_established": true,
-            "message": "Successfully subscribed to webview visibility events",
-            "type": "subscription",
-            "service": "UiService",
-            "method": "subscribeToDidBecomeVisible"
-        }))
-    }
-    
-    async fn scroll_to_settings(&mut self, _message: &Value) -> GrpcResult<Value> {
-        log_debug("[UiService] scrollToSettings - returning placeholder response");
-        Ok(serde_json::json!({
-            "success": true,
-            "message": "Settings scrolled",
-            "service": "UiService",
-            "method": "scrollToSettings"
-        }))
-    }
-    
-    async fn on_did_show_announcement(&mut self) -> GrpcResult<Value> {
-        log_debug("[UiService] onDidShowAnnouncement - returning placeholder response");
-        Ok(serde_json::json!({
-            "value": false,
-            "success": true,
-            "service": "UiService",
-            "method": "onDidShowAnnouncement"
-        }))
-    }
-    
-    async fn get_webview_html(&mut self) -> GrpcResult<Value> {
-        log_debug("[UiService] getWebviewHtml - returning placeholder response");
-        Ok(serde_json::json!({
-            "html": "<html><body>Placeholder</body></html>",
-            "success": true,
-            "service": "UiService",
-            "method": "getWebviewHtml"
-        }))
-    }
-    
-    async fn open_url(&mut self, _message: &Value) -> GrpcResult<Value> {
-        log_debug("[UiService] openUrl - returning placeholder response");
-        Ok(serde_json::json!({
-            "success": true,
-            "service": "UiService",
-            "method": "openUrl"
-        }))
-    }
-    
-    async fn open_walkthrough(&mut self) -> GrpcResult<Value> {
-        log_debug("[UiService] openWalkthrough - returning placeholder response");
-        Ok(serde_json::json!({
-            "success": true,
-            "service": "UiService",
-            "method": "openWalkthrough"
-        }))
-    }
 
     // 辅助方法：处理空流（用于事件订阅）
     async fn handle_empty_stream(
         mut stream: tonic::Streaming<crate::grpc_client::cline::Empty>,
-        stream_name: &str
+        stream_name: &str,
+        config: Option<StreamConfig>
     ) -> GrpcResult<()> {
         log_debug(&format!("[UiService] Starting {} stream processing", stream_name));
         
         let mut event_count = 0;
         
-        while let Some(_event_result) = stream.message().await.map_err(|e| {
+        while let Some(event_result) = stream.message().await.map_err(|e| {
             log_error(&format!("[UiService] {} stream error: {}", stream_name, e));
             format!("{} stream error: {}", stream_name, e)
         })? {
             event_count += 1;
             log_debug(&format!("[UiService] Received {} event #{}", stream_name, event_count));
+            if let Some(ref config) = config {
+                config.send(to_json(&event_result)?).await;
+            }
         }
         
         log_success(&format!(
@@ -598,7 +434,8 @@ impl UiServiceHandler {
     // 辅助方法：处理字符串流
     async fn handle_string_stream(
         mut stream: tonic::Streaming<crate::grpc_client::cline::String>,
-        stream_name: &str
+        stream_name: &str,
+        config: Option<StreamConfig>
     ) -> GrpcResult<()> {
         log_debug(&format!("[UiService] Starting {} string stream processing", stream_name));
         
@@ -615,6 +452,9 @@ impl UiServiceHandler {
                 message_count,
                 message_result.value.chars().take(50).collect::<String>()
             ));
+            if let Some(ref config) = config {
+                config.send(to_json(&message_result)?).await;
+            }
         }
         
         log_success(&format!(
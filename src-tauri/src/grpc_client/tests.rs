@@ -23,6 +23,7 @@ mod tests {
             },
             enable_performance_monitoring: true,
             max_concurrent_requests: 5,
+            ..Default::default()
         }
     }
 
@@ -36,6 +37,27 @@ mod tests {
         assert_eq!(connection_info["connection_failures"], 0);
     }
 
+    #[test]
+    async fn test_client_with_endpoint() {
+        let client = ClineGrpcClient::with_endpoint("http://127.0.0.1:41234");
+        let connection_info = client.get_connection_info();
+
+        assert_eq!(connection_info["endpoint"], "http://127.0.0.1:41234");
+        assert_eq!(connection_info["connected"], false);
+    }
+
+    #[test]
+    async fn test_set_endpoint_is_shared_by_clones() {
+        let client = ClineGrpcClient::with_endpoint("http://127.0.0.1:41234");
+        let shared = client.clone();
+
+        // cline-core 换端口后，所有克隆都连接新地址
+        client.set_endpoint("http://127.0.0.1:41235");
+        assert_eq!(shared.endpoint(), "http://127.0.0.1:41235");
+        assert_eq!(shared.get_connection_info()["endpoint"], "http://127.0.0.1:41235");
+        assert_eq!(shared.get_connection_info()["connected"], false);
+    }
+
     #[test]
     async fn test_client_with_custom_config() {
         let config = create_test_config();
@@ -179,6 +201,21 @@ mod tests {
         assert!(!client.is_cacheable("unknown_method"));
     }
 
+    #[test]
+    async fn test_only_mutating_methods_invalidate_cache() {
+        let client = ClineGrpcClient::new();
+        
+        // 查询方法不清空缓存
+        for method in ["getOllamaModels", "searchFiles", "getRelativePaths", "checkIsImageUrl", "fetchOpenGraphData"] {
+            assert!(!client.invalidates_cache(method), "{}", method);
+        }
+        
+        // 可能修改状态的方法清空缓存
+        for method in ["newTask", "togglePlanActMode", "checkpointRestore", "getter", "toggleMcpServer"] {
+            assert!(client.invalidates_cache(method), "{}", method);
+        }
+    }
+
     #[test]
     fn test_connection_error_detection() {
         let client = ClineGrpcClient::new();
@@ -268,7 +305,6 @@ mod integration_tests {
         connection::{ClineGrpcClient, ConnectionConfig},
         types::StreamConfig,
     };
-    use std::sync::Arc;
     use std::time::Duration;
     use serde_json::json;
     use tokio::test;
@@ -283,7 +319,7 @@ mod integration_tests {
             ..Default::default()
         };
         
-        let mut client = ClineGrpcClient::with_config(config);
+        let client = ClineGrpcClient::with_config(config);
         
         // 尝试连接应该失败
         let result = client.connect().await;
@@ -295,9 +331,65 @@ mod integration_tests {
         assert!(connection_info["connection_failures"].as_u64().unwrap() > 0);
     }
 
+    #[test]
+    async fn test_cloned_clients_share_connection_and_stats() {
+        let config = ConnectionConfig {
+            endpoint: "http://127.0.0.1:1".to_string(),
+            connect_timeout: Duration::from_millis(100),
+            retry_config: crate::grpc_client::utils::RetryConfig::with_delays(
+                0,
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+            ),
+            ..Default::default()
+        };
+        let client = ClineGrpcClient::with_config(config);
+        let shared = client.clone();
+        
+        // 通过克隆发送的请求记录在同一份状态和统计中
+        let result = shared.handle_request("cline.StateService", "getLatestState", &json!({})).await;
+        assert!(result.is_err());
+        
+        let connection_info = client.get_connection_info();
+        assert_eq!(connection_info["connection_failures"], 1);
+        assert_eq!(connection_info["active_requests"], 0);
+        assert_eq!(client.get_performance_stats()["request_count"], 1);
+        assert_eq!(client.get_performance_stats()["error_count"], 1);
+    }
+
+    #[test]
+    async fn test_waiting_requests_share_failed_connection_attempt() {
+        let config = ConnectionConfig {
+            endpoint: "http://127.0.0.1:1".to_string(),
+            connect_timeout: Duration::from_millis(100),
+            retry_config: crate::grpc_client::utils::RetryConfig::with_delays(
+                3,
+                Duration::from_millis(200),
+                Duration::from_millis(200),
+            ),
+            ..Default::default()
+        };
+        let client = ClineGrpcClient::with_config(config);
+        
+        // 一整轮重试约 600ms；等待中的请求复用这一轮的结果，而不是依次各自重试
+        let started = std::time::Instant::now();
+        let handles: Vec<_> = (0..5).map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                client.handle_request("cline.StateService", "getLatestState", &json!({})).await
+            })
+        }).collect();
+        for result in futures::future::join_all(handles).await {
+            assert!(result.unwrap().is_err());
+        }
+        
+        assert!(started.elapsed() < Duration::from_millis(1500), "requests took {:?}", started.elapsed());
+        assert_eq!(client.get_connection_info()["connection_failures"], 1);
+    }
+
     #[test]
     async fn test_request_without_connection() {
-        let mut client = ClineGrpcClient::new();
+        let client = ClineGrpcClient::new();
         
         // 不先连接，直接发送请求
         let result = client.handle_request(
@@ -316,13 +408,12 @@ mod integration_tests {
             max_concurrent_requests: 2, // 限制为2个并发请求
             ..Default::default()
         };
-        let client = Arc::new(tokio::sync::Mutex::new(ClineGrpcClient::with_config(config)));
+        let client = ClineGrpcClient::with_config(config);
         
-        // 模拟多个并发请求
+        // 模拟多个并发请求，克隆的客户端共享同一个并发计数
         let handles: Vec<_> = (0..5).map(|i| {
             let client = client.clone();
             tokio::spawn(async move {
-                let mut client = client.lock().await;
                 client.handle_request(
                     "cline.UiService",
                     "subscribeToPartialMessage",
@@ -343,4 +434,42 @@ mod integration_tests {
         // 至少应该有一些请求被拒绝
         assert!(errors.len() > 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    async fn test_wait_until_serving_times_out() {
+        // 端口上没有服务，应在超时后返回错误
+        let result = crate::grpc_client::health::wait_until_serving(
+            "http://127.0.0.1:1",
+            Duration::from_millis(300),
+            Duration::from_millis(50),
+        ).await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("did not become ready"));
+    }
+
+    #[test]
+    async fn test_wait_until_serving_detects_health_service() {
+        // 先占用一个空闲端口再释放，供测试服务器使用
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let (_health_reporter, health_service) = tonic_health::server::health_reporter();
+        tokio::spawn(async move {
+            let _ = tonic::transport::Server::builder()
+                .add_service(health_service)
+                .serve(addr)
+                .await;
+        });
+
+        let result = crate::grpc_client::health::wait_until_serving(
+            &format!("http://{}", addr),
+            Duration::from_secs(5),
+            Duration::from_millis(50),
+        ).await;
+
+        assert!(result.is_ok());
+    }
+}
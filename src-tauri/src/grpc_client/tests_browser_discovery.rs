@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod browser_discovery_tests {
+    use crate::grpc_client::{
+        browser_discovery::{find_chrome_with, ChromeDiscovery},
+        error::{ErrorCode, GrpcError},
+    };
+    use crate::grpc_client::tests_utils::serve_once;
+    use std::net::TcpListener;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    #[test]
+    fn test_find_chrome_prefers_path() {
+        let exists = |path: &Path| {
+            path == Path::new("/usr/local/bin/chromium") || path == Path::new("/opt/google/chrome/chrome")
+        };
+
+        let found = find_chrome_with(Some("/usr/bin:/usr/local/bin"), None, exists);
+        assert_eq!(found, Some(PathBuf::from("/usr/local/bin/chromium")));
+
+        // 不在 PATH 中时查找常见安装目录
+        let found = find_chrome_with(Some("/usr/bin"), None, exists);
+        assert_eq!(found, Some(PathBuf::from("/opt/google/chrome/chrome")));
+    }
+
+    #[test]
+    fn test_find_chrome_in_flatpak_and_snap() {
+        let home = Path::new("/home/user");
+        let user_flatpak = home.join(".local/share/flatpak/exports/bin/org.chromium.Chromium");
+        let found = find_chrome_with(None, Some(home), |path| path == user_flatpak);
+        assert_eq!(found, Some(user_flatpak.clone()));
+
+        let found = find_chrome_with(None, Some(home), |path| path == Path::new("/snap/bin/chromium"));
+        assert_eq!(found, Some(PathBuf::from("/snap/bin/chromium")));
+
+        assert_eq!(find_chrome_with(Some("/usr/bin"), Some(home), |_| false), None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_devtools_endpoint() {
+        let (url, _) = serve_once(r#"{"Browser":"Chrome/126.0.6478.126","webSocketDebuggerUrl":"ws://127.0.0.1:9222/devtools/browser/abc"}"#);
+        let discovery = ChromeDiscovery::default();
+
+        let result = discovery.test_connection(&format!("{}/", url)).await;
+        assert_eq!(result["success"], true);
+        assert_eq!(result["message"], "Connected to Chrome/126.0.6478.126");
+        assert_eq!(result["endpoint"], "ws://127.0.0.1:9222/devtools/browser/abc");
+    }
+
+    #[tokio::test]
+    async fn test_probe_rejects_other_servers() {
+        let (url, _) = serve_once(r#"{"status":"ok"}"#);
+        let discovery = ChromeDiscovery::new(Duration::from_millis(500));
+
+        let error = discovery.probe(&url).await.unwrap_err();
+        assert_eq!(GrpcError::classify(error.as_ref()).code, ErrorCode::Unavailable);
+
+        // 端口上没有服务
+        let closed = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let result = discovery.test_connection(&closed).await;
+        assert_eq!(result["success"], false);
+        assert!(result["message"].as_str().unwrap().contains(&closed));
+    }
+}
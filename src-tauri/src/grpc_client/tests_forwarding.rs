@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod forwarding_tests {
+    use crate::grpc_client::{
+        connection::{ClineGrpcClient, ConnectionConfig},
+        error::{ErrorCode, GrpcError},
+        reflection::{find_method, json_to_message, message_to_json, DynamicCodec},
+        utils::RetryConfig,
+    };
+    use prost_reflect::{DynamicMessage, MethodDescriptor};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::marker::PhantomData;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+    use tonic::codegen::{empty_body, http, Body, BoxFuture, Service, StdError};
+    use tonic::server::{Grpc, NamedService, UnaryService};
+    use tonic::{Request, Response, Status};
+
+    // 模拟的服务名，每个服务单独注册到 tonic 路由中
+    trait StubName {
+        const NAME: &'static str;
+    }
+
+    #[derive(Clone)]
+    struct TaskService;
+
+    impl StubName for TaskService {
+        const NAME: &'static str = "cline.TaskService";
+    }
+
+    #[derive(Clone)]
+    struct SlashService;
+
+    impl StubName for SlashService {
+        const NAME: &'static str = "cline.SlashService";
+    }
+
+    #[derive(Clone)]
+    struct CommandsService;
+
+    impl StubName for CommandsService {
+        const NAME: &'static str = "cline.CommandsService";
+    }
+
+    #[derive(Clone)]
+    struct WebService;
+
+    impl StubName for WebService {
+        const NAME: &'static str = "cline.WebService";
+    }
+
+    // 收到的请求，按 方法名 -> 请求 JSON 记录
+    type Calls = Arc<Mutex<Vec<(String, Value)>>>;
+
+    // 模拟 cline-core：按描述符解码任意一元请求，返回预设的响应，没有预设时返回默认消息
+    // 预设为 null 的方法返回 Unimplemented，模拟不支持该 RPC 的 cline-core
+    #[derive(Clone)]
+    struct StubService<N> {
+        responses: Arc<HashMap<&'static str, Value>>,
+        calls: Calls,
+        _name: PhantomData<N>,
+    }
+
+    impl<N: StubName, B> Service<http::Request<B>> for StubService<N>
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<B>) -> Self::Future {
+            let name = request.uri().path().rsplit('/').next().unwrap_or_default().to_string();
+            let method = match find_method(N::NAME, &name) {
+                Ok(method) => method,
+                Err(_) => {
+                    return Box::pin(async {
+                        Ok(http::Response::builder()
+                            .status(200)
+                            .header("grpc-status", "12")
+                            .header("content-type", "application/grpc")
+                            .body(empty_body())
+                            .unwrap())
+                    })
+                }
+            };
+
+            let handler = StubMethod {
+                name,
+                method: method.clone(),
+                responses: self.responses.clone(),
+                calls: self.calls.clone(),
+            };
+            Box::pin(async move {
+                let mut grpc = Grpc::new(DynamicCodec::new(method.input()));
+                Ok(grpc.unary(handler, request).await)
+            })
+        }
+    }
+
+    impl<N: StubName> NamedService for StubService<N> {
+        const NAME: &'static str = N::NAME;
+    }
+
+    struct StubMethod {
+        name: String,
+        method: MethodDescriptor,
+        responses: Arc<HashMap<&'static str, Value>>,
+        calls: Calls,
+    }
+
+    impl UnaryService<DynamicMessage> for StubMethod {
+        type Response = DynamicMessage;
+        type Future = BoxFuture<Response<DynamicMessage>, Status>;
+
+        fn call(&mut self, request: Request<DynamicMessage>) -> Self::Future {
+            let result = message_to_json(request.get_ref())
+                .map(|request| self.calls.lock().unwrap().push((self.name.clone(), request)))
+                .map_err(|e| Status::internal(e.message))
+                .and_then(|_| match self.responses.get(self.name.as_str()) {
+                    Some(Value::Null) => Err(Status::unimplemented(format!("{} is not supported", self.name))),
+                    response => json_to_message(self.method.output(), response.unwrap_or(&json!({})))
+                        .map(Response::new)
+                        .map_err(|e| Status::internal(e.message)),
+                });
+            Box::pin(async move { result })
+        }
+    }
+
+    fn stub<N>(responses: &Arc<HashMap<&'static str, Value>>, calls: &Calls) -> StubService<N> {
+        StubService { responses: responses.clone(), calls: calls.clone(), _name: PhantomData }
+    }
+
+    // 启动模拟的 cline-core，返回连接到它的客户端和请求记录
+    async fn connect_to_stub(responses: HashMap<&'static str, Value>) -> (ClineGrpcClient, Calls) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let responses = Arc::new(responses);
+        let calls = Calls::default();
+
+        let router = tonic::transport::Server::builder()
+            .add_service(stub::<TaskService>(&responses, &calls))
+            .add_service(stub::<CommandsService>(&responses, &calls))
+            .add_service(stub::<SlashService>(&responses, &calls))
+            .add_service(stub::<WebService>(&responses, &calls));
+        tokio::spawn(async move {
+            let _ = router
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+                .await;
+        });
+
+        let config = ConnectionConfig {
+            endpoint: format!("http://{}", addr),
+            connect_timeout: Duration::from_secs(2),
+            retry_config: RetryConfig::with_delays(0, Duration::from_millis(10), Duration::from_millis(10)),
+            ..Default::default()
+        };
+        (ClineGrpcClient::with_config(config), calls)
+    }
+
+    #[tokio::test]
+    async fn test_task_requests_are_forwarded() {
+        let responses = HashMap::from([("getTaskHistory", json!({ "totalCount": 2 }))]);
+        let (client, calls) = connect_to_stub(responses).await;
+
+        // TaskService 由专门处理器校验后转发，请求和响应都按 proto3 JSON 转换
+        client
+            .handle_request("cline.TaskService", "newTask", &json!({ "text": "hello", "images": [], "files": [] }))
+            .await
+            .unwrap();
+        client
+            .handle_request("cline.TaskService", "askResponse", &json!({ "responseType": "yesButtonClicked" }))
+            .await
+            .unwrap();
+        let history = client
+            .handle_request("cline.TaskService", "getTaskHistory", &json!({ "favoritesOnly": true }))
+            .await
+            .unwrap();
+        assert_eq!(history["totalCount"], 2);
+
+        let calls = calls.lock().unwrap();
+        let methods: Vec<&str> = calls.iter().map(|(method, _)| method.as_str()).collect();
+        assert_eq!(methods, ["newTask", "askResponse", "getTaskHistory"]);
+        assert_eq!(calls[0].1["text"], "hello");
+        assert_eq!(calls[1].1["responseType"], "yesButtonClicked");
+        assert_eq!(calls[2].1["favoritesOnly"], true);
+    }
+
+    #[tokio::test]
+    async fn test_slash_and_web_requests_are_forwarded() {
+        let url = "https://example.com/cat.png";
+        let responses = HashMap::from([("checkIsImageUrl", json!({ "isImage": true, "url": url }))]);
+        let (client, calls) = connect_to_stub(responses).await;
+
+        // SlashService 和 WebService 没有专门处理器，全部通过描述符转发
+        client
+            .handle_request("cline.SlashService", "condense", &json!({ "value": "" }))
+            .await
+            .unwrap();
+        let image = client
+            .handle_request("cline.WebService", "checkIsImageUrl", &json!({ "value": url }))
+            .await
+            .unwrap();
+        assert_eq!(image, json!({ "isImage": true, "url": url }));
+
+        let calls = calls.lock().unwrap();
+        let methods: Vec<&str> = calls.iter().map(|(method, _)| method.as_str()).collect();
+        assert_eq!(methods, ["condense", "checkIsImageUrl"]);
+        assert_eq!(calls[1].1["value"], url);
+    }
+
+    #[tokio::test]
+    async fn test_editor_commands_are_forwarded() {
+        let (client, calls) = connect_to_stub(HashMap::new()).await;
+
+        // 编辑器命令携带选中的代码，通过描述符转发给 cline-core
+        for method in ["addToCline", "fixWithCline", "explainWithCline", "improveWithCline"] {
+            client
+                .handle_request("cline.CommandsService", method, &json!({ "selectedText": "fn main() {}" }))
+                .await
+                .unwrap();
+        }
+
+        let calls = calls.lock().unwrap();
+        let methods: Vec<&str> = calls.iter().map(|(method, _)| method.as_str()).collect();
+        assert_eq!(methods, ["addToCline", "fixWithCline", "explainWithCline", "improveWithCline"]);
+        assert!(calls.iter().all(|(_, request)| request["selectedText"] == "fn main() {}"));
+    }
+
+    #[tokio::test]
+    async fn test_unimplemented_status_from_core_is_not_retried() {
+        let responses = HashMap::from([("showTaskWithId", Value::Null)]);
+        let (client, calls) = connect_to_stub(responses).await;
+
+        // 专门处理器的 RPC 被 cline-core 拒绝时直接返回，不会再通过描述符重发一次
+        let result = client
+            .handle_request("cline.TaskService", "showTaskWithId", &json!({ "value": "1712345678901" }))
+            .await;
+        let error = GrpcError::classify(result.unwrap_err().as_ref());
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+        assert!(error.message.contains("showTaskWithId is not supported"));
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+}
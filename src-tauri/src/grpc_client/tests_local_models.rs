@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod local_models_tests {
+    use crate::grpc_client::{
+        connection::{ClineGrpcClient, ConnectionConfig},
+        error::{ErrorCode, GrpcError},
+        local_models::{merge_model_lists, LocalModelDiscovery, DEFAULT_DISCOVERY_TIMEOUT},
+        services::ModelsServiceHandler,
+    };
+    use crate::settings::LocalModelsSettings;
+    use crate::grpc_client::tests_utils::serve_once;
+    use serde_json::json;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    // 没有服务监听的地址
+    fn unreachable_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_ollama_models_from_tags() {
+        let (url, received) = serve_once(r#"{"models":[{"name":"llama3:latest"},{"name":"qwen2.5-coder:7b"},{"name":"llama3:latest"}]}"#);
+        let discovery = LocalModelDiscovery::default();
+
+        // 请求中的地址优先于默认地址，末尾的 / 会被去掉
+        let models = discovery.ollama_models(Some(&format!("{}/", url))).await.unwrap();
+        assert_eq!(models, vec!["llama3:latest", "qwen2.5-coder:7b"]);
+        assert_eq!(received.recv().unwrap(), "GET /api/tags HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_lm_studio_models_from_configured_base_url() {
+        let (url, received) = serve_once(r#"{"object":"list","data":[{"id":"qwen2.5-7b-instruct"},{"id":"text-embedding-nomic"}]}"#);
+        let discovery = LocalModelDiscovery::from_settings(&LocalModelsSettings {
+            lm_studio_base_url: Some(url),
+            ..LocalModelsSettings::default()
+        });
+
+        let models = discovery.lm_studio_models(None).await.unwrap();
+        assert_eq!(models, vec!["qwen2.5-7b-instruct", "text-embedding-nomic"]);
+        assert_eq!(received.recv().unwrap(), "GET /v1/models HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_unavailable() {
+        let discovery = LocalModelDiscovery::new(&unreachable_url(), &unreachable_url(), DEFAULT_DISCOVERY_TIMEOUT);
+
+        let error = discovery.ollama_models(None).await.unwrap_err();
+        assert_eq!(GrpcError::classify(error.as_ref()).code, ErrorCode::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_server_times_out() {
+        // 接受连接但从不响应
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let _connection = listener.accept();
+            std::thread::sleep(Duration::from_secs(3));
+        });
+
+        let discovery = LocalModelDiscovery::new(&url, &url, Duration::from_millis(200));
+        let error = discovery.lm_studio_models(None).await.unwrap_err();
+        assert_eq!(GrpcError::classify(error.as_ref()).code, ErrorCode::Timeout);
+    }
+
+    #[test]
+    fn test_merge_keeps_core_order_and_removes_duplicates() {
+        let core = vec!["llama3:latest".to_string(), "mistral:7b".to_string()];
+        let local = vec!["mistral:7b".to_string(), "phi3:mini".to_string(), String::new()];
+
+        assert_eq!(merge_model_lists(core, local), vec!["llama3:latest", "mistral:7b", "phi3:mini"]);
+    }
+
+    #[tokio::test]
+    async fn test_handler_answers_before_cline_core_is_connected() {
+        let (url, _received) = serve_once(r#"{"models":[{"name":"llama3:latest"}]}"#);
+        let mut handler = ModelsServiceHandler::with_local_models(LocalModelDiscovery::default());
+
+        let response = handler.handle_request("getOllamaModels", &json!({ "value": url })).await.unwrap();
+        assert_eq!(response, json!({ "values": ["llama3:latest"] }));
+
+        // 本地服务也不可用时返回 cline-core 的错误
+        let result = handler.handle_request("getLmStudioModels", &json!({ "value": unreachable_url() })).await;
+        assert!(result.unwrap_err().to_string().contains("No ModelsService gRPC client"));
+    }
+
+    #[tokio::test]
+    async fn test_client_answers_local_models_without_waiting_for_connection() {
+        let (url, _received) = serve_once(r#"{"models":[{"name":"llama3:latest"}]}"#);
+        // 默认的重试配置需要约 90 秒才会放弃连接
+        let client = ClineGrpcClient::with_config(ConnectionConfig {
+            endpoint: unreachable_url(),
+            ..ConnectionConfig::default()
+        });
+
+        let started = std::time::Instant::now();
+        let response = client
+            .handle_request("cline.ModelsService", "getOllamaModels", &json!({ "value": url }))
+            .await
+            .unwrap();
+
+        assert_eq!(response, json!({ "values": ["llama3:latest"] }));
+        assert!(started.elapsed() < Duration::from_secs(2), "request took {:?}", started.elapsed());
+    }
+}
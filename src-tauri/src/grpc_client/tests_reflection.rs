@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod reflection_tests {
+    use crate::grpc_client::{
+        error::ErrorCode,
+        cline::{McpServer, McpServers},
+        reflection::{descriptor_pool, find_method, from_json, json_to_message, message_to_json, to_json},
+        types::ServiceType,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_every_service_is_in_descriptor_pool() {
+        let services = [
+            ServiceType::State, ServiceType::Ui, ServiceType::Mcp, ServiceType::File,
+            ServiceType::Models, ServiceType::Task, ServiceType::Account, ServiceType::Browser,
+            ServiceType::Commands, ServiceType::Checkpoints, ServiceType::Slash, ServiceType::Web,
+        ];
+        for service in services {
+            assert!(descriptor_pool().get_service_by_name(service.as_str()).is_some(), "{}", service.as_str());
+        }
+    }
+
+    #[test]
+    fn test_find_method() {
+        let method = find_method("cline.StateService", "getLatestState").unwrap();
+        assert_eq!(method.input().full_name(), "cline.EmptyRequest");
+        assert_eq!(method.output().full_name(), "cline.State");
+        assert!(!method.is_server_streaming());
+
+        let method = find_method("cline.StateService", "subscribeToState").unwrap();
+        assert!(method.is_server_streaming());
+
+        // 首字母大写的方法名也能找到
+        assert!(find_method("cline.StateService", "GetLatestState").is_ok());
+    }
+
+    #[test]
+    fn test_unknown_method_is_not_implemented() {
+        let error = find_method("cline.StateService", "noSuchMethod").unwrap_err();
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+
+        let error = find_method("cline.NoSuchService", "getLatestState").unwrap_err();
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let descriptor = find_method("cline.StateService", "getLatestState").unwrap().output();
+
+        // 同时接受 json_name 和 proto 字段名，未知字段被忽略
+        for request in [json!({"stateJson": "{}"}), json!({"state_json": "{}", "extra": 1})] {
+            let message = json_to_message(descriptor.clone(), &request).unwrap();
+            assert_eq!(message_to_json(&message).unwrap(), json!({"stateJson": "{}"}));
+        }
+
+        // 默认值也会输出
+        let empty = json_to_message(descriptor, &json!({})).unwrap();
+        assert_eq!(message_to_json(&empty).unwrap(), json!({"stateJson": ""}));
+    }
+
+    #[test]
+    fn test_invalid_json_is_invalid_argument() {
+        let descriptor = find_method("cline.StateService", "getLatestState").unwrap().output();
+
+        let error = json_to_message(descriptor, &json!({"stateJson": 5})).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArgument);
+        assert!(error.message.contains("cline.State"));
+    }
+
+    #[test]
+    fn test_typed_messages_use_proto3_json() {
+        let servers = McpServers {
+            mcp_servers: vec![McpServer { name: "demo".to_string(), ..Default::default() }],
+        };
+
+        // 字段名使用 camelCase，与前端 proto 客户端一致
+        let json = to_json(&servers).unwrap();
+        assert_eq!(json["mcpServers"][0]["name"], "demo");
+        assert!(json.get("mcp_servers").is_none());
+
+        let parsed: McpServers = from_json(&json).unwrap();
+        assert_eq!(parsed, servers);
+    }
+}
@@ -1,3 +1,29 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+
+// 只响应一次请求的本地 HTTP 服务，返回服务地址和收到的请求行
+pub fn serve_once(body: &'static str) -> (String, Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (request_line, received) = mpsc::channel();
+    std::thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let mut buffer = [0u8; 4096];
+            let read = stream.read(&mut buffer).unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            let _ = request_line.send(request.lines().next().unwrap_or_default().to_string());
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (url, received)
+}
+
 #[cfg(test)]
 mod utils_tests {
     use crate::grpc_client::utils::{
@@ -184,6 +210,23 @@ mod utils_tests {
         assert!(elapsed <= Duration::from_millis(200)); // 给予一些容差
     }
 
+    #[test]
+    fn test_delay_for_attempt() {
+        let config = RetryConfig {
+            max_retries: 5,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            backoff_multiplier: 2.0,
+        };
+
+        assert_eq!(config.delay_for_attempt(1), Duration::from_millis(100));
+        assert_eq!(config.delay_for_attempt(2), Duration::from_millis(200));
+        assert_eq!(config.delay_for_attempt(3), Duration::from_millis(400));
+        // 超过 max_delay 时被截断
+        assert_eq!(config.delay_for_attempt(4), Duration::from_millis(500));
+        assert_eq!(config.delay_for_attempt(100), Duration::from_millis(500));
+    }
+
     #[test]
     fn test_performance_stats_default() {
         let stats = PerformanceStats::default();
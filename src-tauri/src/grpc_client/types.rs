@@ -1,6 +1,7 @@
 use serde_json::Value;
 use std::error::Error;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
@@ -10,6 +11,9 @@ pub type GrpcResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 // 流式响应回调类型
 pub type StreamCallback = Arc<dyn Fn(Value) -> Result<(), Box<dyn Error + Send + Sync>> + Send + Sync>;
 
+// 流结束回调类型，流因错误结束时传入错误
+pub type StreamCompleteCallback = Arc<dyn Fn(Option<crate::grpc_client::GrpcError>) + Send + Sync>;
+
 // 缓存配置
 #[derive(Debug, Clone)]
 pub struct CacheConfig {
@@ -123,6 +127,11 @@ impl LruCache {
         self.misses = 0;
     }
     
+    // 移除所有条目但保留命中统计
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+    }
+    
     pub fn get_stats(&self) -> serde_json::Value {
         let total_requests = self.hits + self.misses;
         let hit_rate = if total_requests > 0 {
@@ -158,12 +167,109 @@ impl LruCache {
     }
 }
 
+// 流式请求的取消信号，可以在流建立之前或之后触发
+#[derive(Clone)]
+pub struct StreamCancel {
+    sender: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+impl StreamCancel {
+    pub fn new() -> Self {
+        let (sender, _) = tokio::sync::watch::channel(false);
+        Self { sender: Arc::new(sender) }
+    }
+
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    // 等待取消信号
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        while !*receiver.borrow_and_update() {
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // 是否为同一个取消信号（克隆共享同一个信号）
+    pub fn same(&self, other: &StreamCancel) -> bool {
+        Arc::ptr_eq(&self.sender, &other.sender)
+    }
+}
+
+// 流式消息队列中的一项
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    Message(Value),
+    // 流已结束，流因错误结束时带有错误
+    End(Option<crate::grpc_client::GrpcError>),
+}
+
 // 流式请求配置
 #[derive(Clone)]
 pub struct StreamConfig {
     pub enable_streaming: bool,
     pub callback: Option<StreamCallback>,
     pub max_messages: Option<usize>,
+    pub on_complete: Option<StreamCompleteCallback>,
+    // 触发后后台流式任务立即结束，与 cline-core 的订阅随之断开
+    pub cancel: Option<StreamCancel>,
+    // 有界消息队列，队列满时暂停读取上游流，实现背压；设置后不再使用 callback 和 on_complete
+    pub queue: Option<tokio::sync::mpsc::Sender<StreamEvent>>,
+    // 克隆之间共享，有后台任务接管流后为 true；一元方法不会使用流式配置
+    pub started: Arc<AtomicBool>,
+}
+
+impl StreamConfig {
+    // 将每条消息写入有界队列，由队列的消费者负责投递
+    pub fn queued(queue: tokio::sync::mpsc::Sender<StreamEvent>) -> Self {
+        Self {
+            enable_streaming: true,
+            callback: None,
+            max_messages: None,
+            on_complete: None,
+            cancel: None,
+            queue: Some(queue),
+            started: Arc::default(),
+        }
+    }
+
+    // 标记流已由后台任务接管，之后的消息和结束标记由该任务发送
+    pub fn mark_started(&self) {
+        self.started.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    // 转发一条流式消息；使用队列时会等待队列有空位
+    pub async fn send(&self, value: Value) {
+        if let Some(ref queue) = self.queue {
+            if queue.send(StreamEvent::Message(value)).await.is_err() {
+                crate::grpc_client::utils::log_debug("Stream queue closed, dropping message");
+            }
+        } else if let Some(ref callback) = self.callback {
+            if let Err(e) = callback(value) {
+                crate::grpc_client::utils::log_debug(&format!("Stream callback error: {}", e));
+            }
+        }
+    }
+
+    // 通知调用方流已结束
+    pub async fn complete(&self, error: Option<crate::grpc_client::GrpcError>) {
+        if let Some(ref queue) = self.queue {
+            let _ = queue.send(StreamEvent::End(error)).await;
+        } else if let Some(ref on_complete) = self.on_complete {
+            on_complete(error);
+        }
+    }
 }
 
 // 手动实现 Debug，因为 StreamCallback 无法自动 derive Debug
@@ -173,6 +279,10 @@ impl std::fmt::Debug for StreamConfig {
             .field("enable_streaming", &self.enable_streaming)
             .field("callback", &if self.callback.is_some() { "Some(Fn)" } else { "None" })
             .field("max_messages", &self.max_messages)
+            .field("on_complete", &if self.on_complete.is_some() { "Some(Fn)" } else { "None" })
+            .field("cancelled", &self.cancel.as_ref().map(|cancel| cancel.is_cancelled()))
+            .field("queued", &self.queue.is_some())
+            .field("started", &self.is_started())
             .finish()
     }
 }
@@ -215,13 +325,17 @@ impl ServiceType {
 
 // gRPC 请求响应的标准接口
 // 使用枚举而不是 trait object 来避免 dyn compatibility 问题
-#[derive(Debug)]
+// 处理器只持有基于同一个 Channel 的 tonic 客户端，克隆的开销很小
+#[derive(Debug, Clone)]
 pub enum ServiceHandler {
     State(crate::grpc_client::services::StateServiceHandler),
     Ui(crate::grpc_client::services::UiServiceHandler),
     Mcp(crate::grpc_client::services::McpServiceHandler),
-    Account(crate::grpc_client::services::AccountServiceHandler),
     Models(crate::grpc_client::services::ModelsServiceHandler),
+    Task(crate::grpc_client::services::TaskServiceHandler),
+    File(crate::grpc_client::services::FileServiceHandler),
+    Checkpoints(crate::grpc_client::services::CheckpointsServiceHandler),
+    Browser(crate::grpc_client::services::BrowserServiceHandler),
 }
 
 impl ServiceHandler {
@@ -234,8 +348,11 @@ impl ServiceHandler {
             ServiceHandler::State(handler) => handler.handle_request_with_config(method, message, stream_config).await,
             ServiceHandler::Ui(handler) => handler.handle_request_with_config(method, message, stream_config).await,
             ServiceHandler::Mcp(handler) => handler.handle_request_with_config(method, message, stream_config).await,
-            ServiceHandler::Account(handler) => handler.handle_request_with_config(method, message, stream_config).await,
             ServiceHandler::Models(handler) => handler.handle_request_with_config(method, message, stream_config).await,
+            ServiceHandler::Task(handler) => handler.handle_request_with_config(method, message, stream_config).await,
+            ServiceHandler::File(handler) => handler.handle_request_with_config(method, message, stream_config).await,
+            ServiceHandler::Checkpoints(handler) => handler.handle_request_with_config(method, message, stream_config).await,
+            ServiceHandler::Browser(handler) => handler.handle_request_with_config(method, message, stream_config).await,
         }
     }
 }
\ No newline at end of file
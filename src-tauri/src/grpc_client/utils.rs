@@ -1,6 +1,6 @@
 use std::time::{Duration, Instant};
 use tokio::time::{timeout, sleep};
-use crate::grpc_client::GrpcResult;
+use crate::grpc_client::{GrpcError, GrpcResult, StreamConfig};
 
 // 公共的超时配置 - 增加超时时间以适应服务启动
 pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
@@ -51,6 +51,16 @@ impl RetryConfig {
             backoff_multiplier: 2.0,
         }
     }
+
+    // 计算第 attempt 次重试（从 1 开始）前的退避延迟，不超过 max_delay
+    pub fn delay_for_attempt(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let delay_ms = self.initial_delay.as_millis() as f64 * (self.backoff_multiplier as f64).powi(exponent);
+        std::cmp::min(
+            Duration::from_millis(delay_ms.min(u64::MAX as f64) as u64),
+            self.max_delay
+        )
+    }
 }
 
 // 带超时的异步操作包装器
@@ -64,8 +74,8 @@ where
 {
     timeout(timeout_duration, future)
         .await
-        .map_err(|_| format!("{} timeout", operation_name))?
-        .map_err(|e| format!("{} failed: {}", operation_name, e).into())
+        .map_err(|_| GrpcError::timeout(format!("{} timeout", operation_name)))?
+        .map_err(|status| GrpcError::from_status(&status, operation_name).into())
 }
 
 // 带重试的异步操作包装器
@@ -160,6 +170,28 @@ where
     .map_err(|e| e.into())
 }
 
+// 在后台运行流式处理任务，流结束、出错或被取消后通知调用方
+pub fn spawn_stream_task<F>(config: StreamConfig, task: F)
+where
+    F: std::future::Future<Output = GrpcResult<()>> + Send + 'static,
+{
+    config.mark_started();
+    tokio::spawn(async move {
+        let result = match config.cancel.clone() {
+            // 取消时丢弃 task，其中的 tonic::Streaming 随之关闭，cline-core 端的订阅也会结束
+            Some(cancel) => tokio::select! {
+                result = task => result,
+                _ = cancel.cancelled() => {
+                    log_debug("Stream cancelled by caller");
+                    Ok(())
+                }
+            },
+            None => task.await,
+        };
+        config.complete(result.err().map(|e| GrpcError::classify(e.as_ref()))).await;
+    });
+}
+
 // 日志辅助函数
 pub fn log_debug(message: &str) {
     println!("[DEBUG] {}", message);
@@ -2,7 +2,7 @@ use std::pin::Pin;
 use tauri::{AppHandle, Manager, Emitter};
 use tauri_plugin_dialog::DialogExt;
 use tonic::{transport::Server, Request, Response, Status};
-use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tokio_stream::{wrappers::{ReceiverStream, TcpListenerStream}, Stream};
 
 // 包含生成的 protobuf 代码
 pub mod host {
@@ -27,6 +27,35 @@ impl HostBridgeService {
     }
 }
 
+/// 当前打开的工作区，由桌面端在选择或接收工作区时设置
+#[derive(Default)]
+pub struct WorkspaceState {
+    path: std::sync::RwLock<Option<String>>,
+}
+
+impl WorkspaceState {
+    pub fn new(path: Option<String>) -> Self {
+        Self {
+            path: std::sync::RwLock::new(path),
+        }
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.path.read().ok().and_then(|path| path.clone())
+    }
+
+    /// 设置工作区，返回工作区是否发生了变化
+    pub fn set(&self, path: String) -> bool {
+        match self.path.write() {
+            Ok(mut current) if current.as_deref() != Some(path.as_str()) => {
+                *current = Some(path);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
 /// Window 服务实现 - 直接使用 Tauri 对话框 API
 #[tonic::async_trait]
 impl window_service_server::WindowService for HostBridgeService {
@@ -200,13 +229,19 @@ impl workspace_service_server::WorkspaceService for HostBridgeService {
     ) -> Result<Response<GetWorkspacePathsResponse>, Status> {
         log::info!("HostBridge: get_workspace_paths called");
         
-        // 从应用状态或环境变量获取工作区路径
-        let current_dir = std::env::current_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| ".".to_string());
+        // 优先使用桌面端打开的工作区，否则使用当前目录
+        let workspace = self
+            .app_handle
+            .try_state::<WorkspaceState>()
+            .and_then(|state| state.get())
+            .unwrap_or_else(|| {
+                std::env::current_dir()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| ".".to_string())
+            });
             
         Ok(Response::new(GetWorkspacePathsResponse { 
-            paths: vec![current_dir],
+            paths: vec![workspace],
             id: None,
         }))
     }
@@ -338,9 +373,27 @@ impl env_service_server::EnvService for HostBridgeService {
     }
 }
 
-/// 启动 HostBridge gRPC 服务器
+/// 在本地回环地址上绑定 HostBridge 监听端口，port 为 0 时由系统分配
+pub fn bind_listener(port: u16) -> std::io::Result<std::net::TcpListener> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// 启动 HostBridge gRPC 服务器，监听系统分配的端口
 pub async fn start_hostbridge_server(app_handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "127.0.0.1:26041".parse()?;
+    let listener = bind_listener(0)?;
+    serve_hostbridge(app_handle, listener).await
+}
+
+/// 在已绑定的监听器上运行 HostBridge gRPC 服务器
+/// 先绑定再启动子进程，这样 cline-core 启动时端口已确定且可以连接
+pub async fn serve_hostbridge(
+    app_handle: AppHandle,
+    listener: std::net::TcpListener,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    let addr = listener.local_addr()?;
     let service = HostBridgeService::new(app_handle);
     
     log::info!("Starting HostBridge gRPC server on {}", addr);
@@ -359,7 +412,7 @@ pub async fn start_hostbridge_server(app_handle: AppHandle) -> Result<(), Box<dy
         .add_service(diff_service_server::DiffServiceServer::new(service.clone()))
         .add_service(watch_service_server::WatchServiceServer::new(service.clone()))
         .add_service(testing_service_server::TestingServiceServer::new(service.clone()))
-        .serve(addr)
+        .serve_with_incoming(TcpListenerStream::new(listener))
         .await?;
     
     Ok(())
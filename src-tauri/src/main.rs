@@ -2,19 +2,30 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod hostbridge;
+mod bridge;
 mod grpc_client;
+mod native_files;
+mod offline;
+mod ports;
+mod process_manager;
+mod settings;
+mod single_instance;
 
-use std::sync::{Arc, Mutex};
-use tauri::{Manager, Emitter};
+use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
-use tauri_plugin_shell::ShellExt;
-use tauri_plugin_shell::process::{CommandEvent, CommandChild};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use grpc_client::GrpcError;
+use ports::{ServicePorts, SharedServicePorts};
+use process_manager::{SharedProcessManager, create_process_manager};
+use single_instance::{Acquired, HandOff};
 
 
 #[tauri::command]
-async fn select_workspace(app_handle: tauri::AppHandle) -> Result<String, String> {
+async fn select_workspace(
+    app_handle: tauri::AppHandle,
+    process_manager: tauri::State<'_, SharedProcessManager>
+) -> Result<String, String> {
     println!("Opening workspace selection dialog...");
     
     // 使用对话框插件打开文件夹选择对话框
@@ -32,6 +43,14 @@ async fn select_workspace(app_handle: tauri::AppHandle) -> Result<String, String
         Ok(Some(folder_path)) => {
             let path_str = folder_path.to_string();
             println!("Workspace selected: {}", path_str);
+            // 切换 cline-core 使用的工作区
+            let process_manager = process_manager.inner().clone();
+            let path = path_str.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                single_instance::workspace::open_workspace(&app_handle, &process_manager, path)
+            })
+            .await
+            .map_err(|e| format!("Failed to open workspace: {}", e))??;
             Ok(path_str)
         }
         Ok(None) => {
@@ -45,43 +64,24 @@ async fn select_workspace(app_handle: tauri::AppHandle) -> Result<String, String
     }
 }
 
-// 全局进程管理器，用于跟踪所有子进程
-struct ProcessManager {
-    processes: Vec<CommandChild>,
-}
-
-impl ProcessManager {
-    fn new() -> Self {
-        Self {
-            processes: Vec::new(),
-        }
-    }
-
-    fn add_process(&mut self, child: CommandChild) {
-        println!("Adding process to manager, total processes: {}", self.processes.len() + 1);
-        self.processes.push(child);
-    }
-
-    fn kill_all(&mut self) {
-        println!("Attempting to kill {} child processes...", self.processes.len());
-        let mut processes_to_kill = Vec::new();
-        std::mem::swap(&mut processes_to_kill, &mut self.processes);
-        
-        for process in processes_to_kill {
-            match process.kill() {
-                Ok(_) => println!("Successfully killed a child process"),
-                Err(e) => eprintln!("Failed to kill process: {}", e),
-            }
-        }
-        println!("Finished killing all child processes");
-    }
+#[tauri::command]
+async fn open_workspace(
+    app_handle: tauri::AppHandle,
+    process_manager: tauri::State<'_, SharedProcessManager>,
+    path: String
+) -> Result<bool, String> {
+    // 等待旧的 cline-core 退出期间不占用异步运行时的工作线程
+    let process_manager = process_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        single_instance::workspace::open_workspace(&app_handle, &process_manager, path)
+    })
+    .await
+    .map_err(|e| format!("Failed to open workspace: {}", e))?
 }
 
-// 创建一个全局的进程管理器
-type SharedProcessManager = Arc<Mutex<ProcessManager>>;
-
-fn create_process_manager() -> SharedProcessManager {
-    Arc::new(Mutex::new(ProcessManager::new()))
+#[tauri::command]
+async fn get_workspace(workspace: tauri::State<'_, hostbridge::WorkspaceState>) -> Result<Option<String>, String> {
+    Ok(workspace.get())
 }
 
 #[tauri::command]
@@ -91,83 +91,10 @@ async fn start_cline_core(
 ) -> Result<String, String> {
     println!("Starting cline-core process...");
     
-    // 使用shell直接运行node + cline-core.js
-    // 确保工作目录是正确的
-    let cline_core_path = "cline-core.js";
-    let command = app_handle
-        .shell()
-        .command("node")
-        .args([cline_core_path])
-        .current_dir("../cline/dist-standalone"); // 设置正确的工作目录
+    // 启动cline-core，处理其输出，并在意外退出时自动重启
+    let pid = process_manager::cline_core::start_supervised(&app_handle, process_manager.inner())?;
     
-    let (mut rx, child) = command
-        .spawn()
-        .map_err(|e| format!("Failed to spawn cline-core process: {}", e))?;
-    
-    // 将子进程添加到进程管理器中
-    {
-        let mut manager = process_manager.lock().unwrap();
-        manager.add_process(child);
-    }
-    
-    println!("Cline core process started");
-    
-    // 处理cline-core的输出
-    let handle = app_handle.clone();
-    tauri::async_runtime::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            if let Some(window) = handle.get_webview_window("main") {
-                match event {
-                    CommandEvent::Stdout(line) => {
-                        let line_str = String::from_utf8_lossy(&line);
-                        println!("cline-core stdout: {}", line_str);
-                        
-                        // 更灵活的就绪检测条件 - 检测多种可能的就绪信号
-                        if line_str.contains("HostBridge is serving") || 
-                           line_str.contains("ProtoBus gRPC server listening on") ||
-                           line_str.contains("gRPC server listening") ||
-                           line_str.contains("Server started") {
-                            let window_clone = window.clone();
-                            println!("[DEBUG] Detected cline-core ready signal: {}", line_str.trim());
-                            println!("[DEBUG] Emitting cline-core-ready event in 3 seconds...");
-                            // 在发送就绪事件之前，增加等待时间以确保服务完全启动
-                            tauri::async_runtime::spawn(async move {
-                                tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
-                                println!("[DEBUG] Emitting cline-core-ready event now");
-                                match window_clone.emit("cline-core-ready", ()) {
-                                    Ok(_) => println!("[DEBUG] ✅ cline-core-ready event emitted successfully"),
-                                    Err(e) => println!("[DEBUG] ❌ Failed to emit cline-core-ready event: {}", e),
-                                }
-                            });
-                        }
-                        
-                        window
-                            .emit("cline-stdout", line_str.to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Stderr(line) => {
-                        eprintln!("cline-core stderr: {}", String::from_utf8_lossy(&line));
-                        window
-                            .emit("cline-stderr", String::from_utf8_lossy(&line).to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Error(err) => {
-                        eprintln!("cline-core error: {}", err);
-                        window
-                            .emit("cline-error", err.to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Terminated(status) => {
-                        println!("cline-core terminated with status: {:?}", status);
-                        window
-                            .emit("cline-terminated", status)
-                            .expect("failed to emit event");
-                    }
-                    _ => {}
-                }
-            }
-        }
-    });
+    println!("Cline core process started (pid {})", pid);
     
     Ok("Cline core started successfully".to_string())
 }
@@ -177,70 +104,23 @@ async fn start_node_server(
     app_handle: tauri::AppHandle,
     process_manager: tauri::State<'_, SharedProcessManager>,
     script_path: String,
-    args: Vec<String>
+    args: Vec<String>,
+    id: Option<String>
 ) -> Result<String, String> {
     println!("Starting Node.js server: {}", script_path);
     
-    // 创建一个新的命令来启动Node.js进程
-    let command = app_handle
-        .shell()
-        .command("node");
-    
-    // 构建参数
-    let mut all_args = vec![script_path];
-    all_args.extend(args);
-    
-    let command = command.args(all_args);
+    let spec = process_manager::node_server::node_script_spec(script_path, args);
+    let id = {
+        let manager = process_manager.lock().unwrap();
+        id.unwrap_or_else(|| manager.unique_id(&spec.name))
+    };
     
-    let (mut rx, child) = command
-        .spawn()
+    let pid = process_manager::node_server::start(&app_handle, process_manager.inner(), &id, spec)
         .map_err(|e| format!("Failed to spawn Node.js process: {}", e))?;
     
-    // 将子进程添加到进程管理器中
-    {
-        let mut manager = process_manager.lock().unwrap();
-        manager.add_process(child);
-    }
-    
-    println!("Node.js server started");
-    
-    // 处理Node.js进程的输出
-    let handle = app_handle.clone();
-    tauri::async_runtime::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            if let Some(window) = handle.get_webview_window("main") {
-                match event {
-                    CommandEvent::Stdout(line) => {
-                        println!("Node.js stdout: {}", String::from_utf8_lossy(&line));
-                        window
-                            .emit("node-stdout", String::from_utf8_lossy(&line).to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Stderr(line) => {
-                        eprintln!("Node.js stderr: {}", String::from_utf8_lossy(&line));
-                        window
-                            .emit("node-stderr", String::from_utf8_lossy(&line).to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Error(err) => {
-                        eprintln!("Node.js error: {}", err);
-                        window
-                            .emit("node-error", err.to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Terminated(status) => {
-                        println!("Node.js terminated with status: {:?}", status);
-                        window
-                            .emit("node-terminated", status)
-                            .expect("failed to emit event");
-                    }
-                    _ => {}
-                }
-            }
-        }
-    });
+    println!("Node.js server '{}' started (pid {})", id, pid);
     
-    Ok("Node.js server started successfully".to_string())
+    Ok(format!("Node.js server started successfully (id: {})", id))
 }
 
 #[tauri::command]
@@ -252,98 +132,163 @@ async fn start_node_server_sidecar(
     println!("Starting Node.js server sidecar on port {}", port);
     
     // 使用sidecar功能启动Node.js服务器
-    let sidecar_command = app_handle
-        .shell()
-        .sidecar("node-server")
-        .map_err(|e| format!("Failed to get sidecar command: {}", e))?;
-    
-    // 传递端口参数
-    let sidecar_command = sidecar_command.args(&[port.to_string()]);
+    let id = format!("node-server-{}", port);
+    let spec = process_manager::node_server::sidecar_spec(port);
     
-    let (mut rx, child) = sidecar_command
-        .spawn()
+    let pid = process_manager::node_server::start(&app_handle, process_manager.inner(), &id, spec)
         .map_err(|e| format!("Failed to spawn Node.js sidecar: {}", e))?;
     
-    // 将子进程添加到进程管理器中
-    {
-        let mut manager = process_manager.lock().unwrap();
-        manager.add_process(child);
-    }
-    
-    println!("Node.js sidecar started");
-    
-    // 处理sidecar的输出
-    let handle = app_handle.clone();
-    let ready_flag = "NODE_SERVER_READY";
-    let mut server_ready = false;
-    
-    tauri::async_runtime::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            if let Some(window) = handle.get_webview_window("main") {
-                match event {
-                    CommandEvent::Stdout(line) => {
-                        let line_str = String::from_utf8_lossy(&line).to_string();
-                        println!("Node.js sidecar stdout: {}", line_str);
-                        
-                        // 检查服务器是否准备就绪
-                        if !server_ready && line_str.contains(ready_flag) {
-                            server_ready = true;
-                            window
-                                .emit("node-server-ready", port)
-                                .expect("failed to emit ready event");
-                        }
-                        
-                        window
-                            .emit("node-stdout", line_str)
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Stderr(line) => {
-                        eprintln!("Node.js sidecar stderr: {}", String::from_utf8_lossy(&line));
-                        window
-                            .emit("node-stderr", String::from_utf8_lossy(&line).to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Error(err) => {
-                        eprintln!("Node.js sidecar error: {}", err);
-                        window
-                            .emit("node-error", err.to_string())
-                            .expect("failed to emit event");
-                    }
-                    CommandEvent::Terminated(status) => {
-                        println!("Node.js sidecar terminated with status: {:?}", status);
-                        window
-                            .emit("node-terminated", status)
-                            .expect("failed to emit event");
-                    }
-                    _ => {}
-                }
-            }
-        }
-    });
+    println!("Node.js sidecar '{}' started (pid {})", id, pid);
     
     Ok(format!("Node.js sidecar started on port {}", port))
 }
 
+#[tauri::command]
+async fn list_processes(
+    process_manager: tauri::State<'_, SharedProcessManager>
+) -> Result<Vec<process_manager::ProcessInfo>, String> {
+    let manager = process_manager.lock().unwrap();
+    Ok(manager.list())
+}
+
+#[tauri::command]
+async fn stop_process(
+    process_manager: tauri::State<'_, SharedProcessManager>,
+    id: String
+) -> Result<String, String> {
+    println!("Stopping process '{}'...", id);
+    
+    // 先发送 SIGTERM 让 cline-core 保存任务状态，等待宽限期期间不占用异步运行时的工作线程
+    let process_manager = process_manager.inner().clone();
+    let stop_id = id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        process_manager::shutdown::stop_gracefully(&process_manager, &stop_id, true)
+    })
+    .await
+    .map_err(|e| format!("Failed to stop process {}: {}", id, e))??;
+    
+    Ok(format!("Process {} stopped successfully", id))
+}
+
+#[tauri::command]
+async fn restart_process(
+    app_handle: tauri::AppHandle,
+    process_manager: tauri::State<'_, SharedProcessManager>,
+    id: String
+) -> Result<String, String> {
+    println!("Restarting process '{}'...", id);
+    
+    let spec = process_manager
+        .lock()
+        .unwrap()
+        .spec(&id)
+        .ok_or_else(|| format!("Unknown process: {}", id))?;
+    
+    // 与停止进程相同，先优雅退出再重新启动
+    let manager = process_manager.inner().clone();
+    let stop_id = id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        process_manager::shutdown::stop_gracefully(&manager, &stop_id, true)
+    })
+    .await
+    .map_err(|e| format!("Failed to stop process {}: {}", id, e))??;
+    
+    // cline-core 需要重新交给监督任务管理
+    let pid = if id == process_manager::CLINE_CORE_ID {
+        process_manager::cline_core::start_supervised(&app_handle, process_manager.inner())?
+    } else {
+        process_manager::node_server::start(&app_handle, process_manager.inner(), &id, spec)?
+    };
+    
+    println!("Process '{}' restarted (pid {})", id, pid);
+    
+    Ok(format!("Process {} restarted successfully", id))
+}
+
+#[tauri::command]
+async fn tail_process_output(
+    process_manager: tauri::State<'_, SharedProcessManager>,
+    id: String,
+    lines: Option<usize>
+) -> Result<Vec<process_manager::OutputLine>, String> {
+    let manager = process_manager.lock().unwrap();
+    manager.tail(&id, lines.unwrap_or(100))
+}
+
+#[tauri::command]
+async fn get_process_stats(
+    process_manager: tauri::State<'_, SharedProcessManager>
+) -> Result<Vec<process_manager::ProcessStats>, String> {
+    let manager = process_manager.lock().unwrap();
+    Ok(manager.stats())
+}
+
+#[tauri::command]
+async fn read_process_log(
+    process_manager: tauri::State<'_, SharedProcessManager>,
+    id: String,
+    lines: Option<usize>,
+    since: Option<i64>,
+    until: Option<i64>
+) -> Result<Vec<process_manager::OutputLine>, String> {
+    let (dir, rotation) = {
+        let manager = process_manager.lock().unwrap();
+        manager.log_location()?
+    };
+    
+    // 未指定时间范围和行数时，默认返回最后 1000 行
+    let query = process_manager::LogQuery {
+        lines: lines.or(if since.is_none() && until.is_none() { Some(1000) } else { None }),
+        since,
+        until,
+    };
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        process_manager::log_file::read_log(&dir, &id, &rotation, &query)
+            .map_err(|e| format!("Failed to read log for process {}: {}", id, e))
+    })
+    .await
+    .map_err(|e| format!("Failed to read process log: {}", e))?
+}
+
 #[tauri::command]
 async fn stop_all_processes(process_manager: tauri::State<'_, SharedProcessManager>) -> Result<String, String> {
     println!("Stopping all child processes...");
     
-    // 使用作用域来确保锁在函数结束前被释放
-    {
+    // 使用作用域来确保锁在等待子进程退出前被释放
+    let (children, grace_period) = {
         let mut manager = process_manager.lock().unwrap();
-        manager.kill_all();
+        (manager.take_all_children(), manager.shutdown_config().grace_period)
+    };
+    
+    // 先请求子进程树自行退出，超时后再强制结束
+    let survivors = tauri::async_runtime::spawn_blocking(move || {
+        process_manager::shutdown::terminate_gracefully(children, grace_period)
+    })
+    .await
+    .map_err(|e| format!("Failed to stop child processes: {}", e))?;
+    
+    if !survivors.is_empty() {
+        return Err(format!("Some child processes could not be terminated: {:?}", survivors));
     }
     
     Ok("All processes stopped successfully".to_string())
 }
 
 #[tauri::command]
-async fn test_grpc_connection() -> Result<String, String> {
+async fn get_service_ports(ports: tauri::State<'_, SharedServicePorts>) -> Result<ServicePorts, String> {
+    Ok(*ports.read().unwrap())
+}
+
+#[tauri::command]
+async fn is_offline_mode(offline: tauri::State<'_, offline::OfflineState>) -> Result<bool, String> {
+    Ok(offline.enabled())
+}
+
+#[tauri::command]
+async fn test_grpc_connection(client: tauri::State<'_, grpc_client::ClineGrpcClient>) -> Result<String, String> {
     println!("[DEBUG] Testing gRPC connection to cline-core...");
     
-    // 创建新的客户端实例进行测试
-    let mut client = grpc_client::ClineGrpcClient::new();
-    
     let connection_info = client.get_connection_info();
     let performance_stats = client.get_performance_stats();
     let cache_stats = client.get_cache_stats();
@@ -362,6 +307,27 @@ async fn test_grpc_connection() -> Result<String, String> {
     Ok(test_result.to_string())
 }
 
+// 列出当前任务的检查点，供恢复前选择
+#[tauri::command]
+async fn list_checkpoints(
+    client: tauri::State<'_, grpc_client::ClineGrpcClient>,
+    offline: tauri::State<'_, offline::OfflineState>,
+) -> Result<Value, String> {
+    // 检查点来自当前任务消息中的 lastCheckpointHash
+    let state = match offline.responder() {
+        Some(responder) => responder.respond("cline.StateService", "getLatestState"),
+        // 缓存中的状态可能早于最近一次检查点，必须读取最新状态
+        None => client
+            .handle_request_uncached("cline.StateService", "getLatestState", &serde_json::json!({}))
+            .await
+            .map_err(|e| GrpcError::classify(e.as_ref())),
+    }
+    .map_err(|e| e.to_string())?;
+    
+    let state_json = state["stateJson"].as_str().unwrap_or("{}");
+    grpc_client::services::checkpoints_service::checkpoints_from_state(state_json).map_err(|e| e.to_string())
+}
+
 // Webview消息结构体
 #[derive(Debug, Deserialize, Serialize)]
 struct WebviewMessage {
@@ -369,6 +335,8 @@ struct WebviewMessage {
     message_type: String,
     #[serde(rename = "grpc_request")]
     grpc_request: Option<GrpcRequest>,
+    #[serde(rename = "grpc_request_cancel", default)]
+    grpc_request_cancel: Option<GrpcRequestCancel>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -380,13 +348,30 @@ struct GrpcRequest {
     is_streaming: bool,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct GrpcRequestCancel {
+    request_id: String,
+}
+
 #[tauri::command]
 async fn handle_webview_message(
     app_handle: tauri::AppHandle,
+    webview: tauri::WebviewWindow,
+    ports: tauri::State<'_, SharedServicePorts>,
+    client: tauri::State<'_, grpc_client::ClineGrpcClient>,
+    streams: tauri::State<'_, bridge::StreamRegistry>,
+    offline: tauri::State<'_, offline::OfflineState>,
     message: WebviewMessage,
+    on_message: Option<tauri::ipc::JavaScriptChannelId>,
 ) -> Result<Value, String> {
     println!("[DEBUG] Received webview message: type={:?}", message.message_type);
     
+    // 响应只发送给发起请求的 webview
+    let channel = on_message.map(|id| id.channel_on(webview.as_ref().clone()));
+    let sink = bridge::webview_sink(app_handle, webview.label(), channel);
+    // 复制一份当前端口，ProtoBus 端口可能在 cline-core 重启时被重新分配
+    let ports = *ports.read().unwrap();
+    
     // 根据消息类型处理
     let result = match message.message_type.as_str() {
         "grpc_request" => {
@@ -394,66 +379,72 @@ async fn handle_webview_message(
                 println!("[DEBUG] Processing gRPC request: service={}, method={}, request_id={}, is_streaming={}",
                     grpc_request.service, grpc_request.method, grpc_request.request_id, grpc_request.is_streaming);
                 
-                // 根据服务类型转发到不同的端口
-                let forward_result = if grpc_request.service.starts_with("cline.") {
-                    println!("[DEBUG] Forwarding to ProtoBus (26040): {} {}", grpc_request.service, grpc_request.method);
-                    // 转发到ProtoBus (Node.js cline-core on port 26040)
-                    forward_to_protobus(&grpc_request).await
+                // 流式请求的每条消息都通过回调转发给前端，流结束后发送结束标记
+                let stream_config = if grpc_request.is_streaming {
+                    Some(bridge::registered_stream_config(streams.inner(), grpc_request.request_id.clone(), webview.label(), sink.clone()))
+                } else {
+                    None
+                };
+                
+                // 根据服务类型转发到不同的端口；离线模式下 cline.* 请求由内置数据响应
+                let offline_responder = offline.responder().filter(|_| grpc_request.service.starts_with("cline."));
+                let forward_result = if let Some(responder) = offline_responder {
+                    println!("[OFFLINE] Responding from fixtures: {} {}", grpc_request.service, grpc_request.method);
+                    respond_offline(responder, &grpc_request, stream_config.as_ref()).await
+                } else if grpc_request.service.starts_with("cline.") {
+                    println!("[DEBUG] Forwarding to ProtoBus ({}): {} {}", ports.protobus, grpc_request.service, grpc_request.method);
+                    // 转发到ProtoBus (Node.js cline-core)
+                    forward_to_protobus(client.inner(), &grpc_request, stream_config.clone()).await
                 } else if grpc_request.service.starts_with("host.") {
-                    println!("[DEBUG] Forwarding to HostBridge (26041): {} {}", grpc_request.service, grpc_request.method);
-                    // 转发到HostBridge (Rust HostBridge on port 26041)
-                    forward_to_hostbridge(&grpc_request).await
+                    println!("[DEBUG] Forwarding to HostBridge ({}): {} {}", ports.hostbridge, grpc_request.service, grpc_request.method);
+                    // 转发到HostBridge (Rust HostBridge)，只有一条响应，流式请求在下面作为唯一的流消息发送
+                    forward_to_hostbridge(&ports.hostbridge_endpoint(), &grpc_request).await
                 } else {
-                    Err(format!("Unknown service: {}", grpc_request.service))
+                    Err(GrpcError::not_implemented(format!("Unknown service: {}", grpc_request.service)))
                 };
                 
-                // 将结果发送回前端
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let response_message = match forward_result {
-                        Ok(ref response_data) => {
-                            println!("[DEBUG] Sending successful response back to frontend for request_id: {}", grpc_request.request_id);
-                            serde_json::json!({
-                                "type": "grpc_response",
-                                "grpc_response": {
-                                    "request_id": grpc_request.request_id,
-                                    "message": response_data,
-                                    "error": null,
-                                    "is_streaming": grpc_request.is_streaming
-                                }
-                            })
-                        },
-                        Err(ref error_msg) => {
-                            println!("[DEBUG] Sending error response back to frontend for request_id: {}", grpc_request.request_id);
-                            serde_json::json!({
-                                "type": "grpc_response",
-                                "grpc_response": {
-                                    "request_id": grpc_request.request_id,
-                                    "message": null,
-                                    "error": error_msg,
-                                    "is_streaming": false
-                                }
-                            })
+                // 将结果发送回前端；订阅的消息已经由回调逐条发送
+                let response_message = match forward_result {
+                    Ok(ref response_data) if !grpc_request.is_streaming => {
+                        println!("[DEBUG] Sending successful response back to frontend for request_id: {}", grpc_request.request_id);
+                        Some(bridge::grpc_response(&grpc_request.request_id, Some(response_data.clone()), None, false, None))
+                    }
+                    Ok(ref response_data) => {
+                        // 一元方法不会接管流，响应作为唯一的流消息发送，随后发送结束标记
+                        if let Some(config) = &stream_config {
+                            if bridge::finish_unstarted_stream(config, response_data).await {
+                                println!("[DEBUG] Finished unary response as a stream for request_id: {}", grpc_request.request_id);
+                            }
                         }
-                    };
-                    
-                    // 使用 eval 执行 JavaScript 将响应发送到前端
-                    let js_code = format!(
-                        "window.dispatchEvent(new MessageEvent('message', {{ data: {} }}));",
-                        response_message.to_string()
-                    );
-                    
-                    match window.eval(&js_code) {
-                        Ok(_) => println!("[DEBUG] ✅ Response sent to frontend successfully"),
-                        Err(e) => println!("[DEBUG] ❌ Failed to send response to frontend: {}", e),
+                        None
                     }
+                    Err(ref error) => {
+                        println!("[DEBUG] Sending error response back to frontend for request_id: {} ({})", grpc_request.request_id, error.code.as_str());
+                        // 订阅没有建立，不需要再保留登记
+                        streams.cancel(&grpc_request.request_id);
+                        Some(bridge::grpc_response(&grpc_request.request_id, None, Some(error), false, None))
+                    }
+                };
+                if let Some(response_message) = response_message {
+                    sink(response_message);
                 }
                 
                 forward_result
             } else {
-                Err("Missing grpc_request in message".to_string())
+                Err(GrpcError::invalid_argument("Missing grpc_request in message"))
+            }
+        }
+        "grpc_request_cancel" => {
+            if let Some(cancel) = message.grpc_request_cancel {
+                // 取消后后台任务会发送结束标记
+                let cancelled = streams.cancel(&cancel.request_id);
+                println!("[DEBUG] Cancel stream request_id={}, found={}", cancel.request_id, cancelled);
+                Ok(serde_json::json!({ "cancelled": cancelled }))
+            } else {
+                Err(GrpcError::invalid_argument("Missing grpc_request_cancel in message"))
             }
         }
-        _ => Err(format!("Unknown message type: {}", message.message_type)),
+        _ => Err(GrpcError::invalid_argument(format!("Unknown message type: {}", message.message_type))),
     };
     
     // 返回处理结果
@@ -461,26 +452,25 @@ async fn handle_webview_message(
         Ok(response) => Ok(response),
         Err(error) => {
             println!("[DEBUG] Handle webview message error: {}", error);
-            Ok(serde_json::json!({ "error": error }))
+            Ok(serde_json::json!({ "error": error.message, "error_code": error.code }))
         }
     }
 }
 
-async fn forward_to_protobus(grpc_request: &GrpcRequest) -> Result<Value, String> {
-    println!("[DEBUG] Forwarding gRPC request to ProtoBus (26040): service={}, method={}, request_id={}", 
+async fn forward_to_protobus(
+    client: &grpc_client::ClineGrpcClient,
+    grpc_request: &GrpcRequest,
+    stream_config: Option<grpc_client::StreamConfig>
+) -> Result<Value, GrpcError> {
+    println!("[DEBUG] Forwarding gRPC request to ProtoBus: service={}, method={}, request_id={}", 
         grpc_request.service, grpc_request.method, grpc_request.request_id);
     
-    // 为每个请求创建独立的客户端实例，完全避免锁竞争
-    println!("[DEBUG] Creating new gRPC client instance for this request...");
-    let mut client = grpc_client::ClineGrpcClient::new();
-    
-    println!("[DEBUG] Attempting to ensure gRPC client connection...");
-    
-    // 尝试使用真正的 gRPC 连接
-    match client.handle_request(
+    // 共享的客户端复用同一个连接，并发请求之间不需要加锁
+    match client.handle_request_with_config(
         &grpc_request.service,
         &grpc_request.method,
-        &grpc_request.message
+        &grpc_request.message,
+        stream_config
     ).await {
         Ok(response) => {
             println!("[DEBUG] ✅ Real gRPC request successful: service={}, method={}", 
@@ -488,86 +478,39 @@ async fn forward_to_protobus(grpc_request: &GrpcRequest) -> Result<Value, String
             Ok(response)
         }
         Err(e) => {
-            println!("[DEBUG] ❌ Real gRPC request failed: {}, falling back to mock response", e);
-            println!("[DEBUG] Error details: {:?}", e);
-            
-            // 如果 gRPC 连接失败，返回 mock 响应
-            fallback_mock_response(grpc_request)
+            // 真实的错误直接返回给前端，由前端根据错误码展示
+            let error = GrpcError::classify(e.as_ref());
+            println!("[DEBUG] ❌ Real gRPC request failed ({}): {}", error.code.as_str(), error);
+            Err(error)
         }
     }
 }
 
-fn fallback_mock_response(grpc_request: &GrpcRequest) -> Result<Value, String> {
-    println!("[DEBUG] ❌ Using fallback mock response for: {}.{}", 
-        grpc_request.service, grpc_request.method);
-        
-    let current_timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64;
-    
-    // 对于某些方法，直接返回错误而不是无效响应
-    match (grpc_request.service.as_str(), grpc_request.method.as_str()) {
-        ("cline.UiService", "subscribeToPartialMessage") => {
-            println!("[DEBUG] subscribeToPartialMessage - returning error to avoid timestamp validation");
-            return Err("No partial messages available".to_string());
+// 离线模式下的 cline.* 请求；订阅发送一次数据后保持打开，没有数据的订阅不发送消息
+async fn respond_offline(
+    responder: &offline::OfflineResponder,
+    grpc_request: &GrpcRequest,
+    stream_config: Option<&grpc_client::StreamConfig>
+) -> Result<Value, GrpcError> {
+    match stream_config {
+        Some(config) => {
+            // 内置数据模拟保持打开的订阅，不发送结束标记
+            config.mark_started();
+            if let Some(fixture) = responder.fixture(&grpc_request.service, &grpc_request.method) {
+                config.send(fixture.clone()).await;
+            }
+            Ok(Value::Null)
         }
-        _ => {}
+        None => responder.respond(&grpc_request.service, &grpc_request.method),
     }
-    
-    // 根据不同的服务和方法返回不同的 mock 响应
-    let mock_response = match (grpc_request.service.as_str(), grpc_request.method.as_str()) {
-        ("cline.StateService", "subscribeToState") | ("cline.StateService", "getLatestState") => {
-            // 返回包含 state_json 的状态响应
-            serde_json::json!({
-                "state_json": serde_json::json!({
-                    "version": "2.0.0",
-                    "clineMessages": [],
-                    "taskHistory": [],
-                    "apiConfiguration": {},
-                    "customInstructions": "",
-                    "alwaysAllowReadOnly": false,
-                    "alwaysAllowWrite": false,
-                    "alwaysAllowExecute": false,
-                    "alwaysAllowBrowser": false,
-                    "alwaysAllowMcp": false,
-                    "didShowWelcome": true,
-                    "shouldShowAnnouncement": false,
-                    "experimentalTerminal": false,
-                    "timestamp": current_timestamp
-                }).to_string()
-            })
-        }
-        _ => {
-            // 其他方法的默认 mock 响应，确保时间戳是有效的正数
-            serde_json::json!({
-                "ts": current_timestamp,
-                "type": 1,
-                "ask": 0,
-                "say": 4,
-                "text": format!("Mock response for {}.{}", grpc_request.service, grpc_request.method),
-                "reasoning": "",
-                "images": [],
-                "files": [],
-                "partial": false,
-                "lastCheckpointHash": "",
-                "isCheckpointCheckedOut": false,
-                "isOperationOutsideWorkspace": false,
-                "conversationHistoryIndex": 0
-            })
-        }
-    };
-    
-    println!("[DEBUG] Fallback mock response with ts={}: {}", current_timestamp, mock_response);
-    Ok(mock_response)
 }
 
-async fn forward_to_hostbridge(grpc_request: &GrpcRequest) -> Result<Value, String> {
-    println!("[DEBUG] Forwarding to HostBridge (26041): service={}, method={}, request_id={}", 
-        grpc_request.service, grpc_request.method, grpc_request.request_id);
+async fn forward_to_hostbridge(endpoint: &str, grpc_request: &GrpcRequest) -> Result<Value, GrpcError> {
+    println!("[DEBUG] Forwarding to HostBridge ({}): service={}, method={}, request_id={}", 
+        endpoint, grpc_request.service, grpc_request.method, grpc_request.request_id);
     
     // 构建请求URL
-    let url = format!("http://127.0.0.1:26041/{}/{}", grpc_request.service, grpc_request.method);
+    let url = format!("{}/{}/{}", endpoint, grpc_request.service, grpc_request.method);
     println!("[DEBUG] HostBridge URL: {}", url);
     
     // 创建HTTP客户端
@@ -587,7 +530,7 @@ async fn forward_to_hostbridge(grpc_request: &GrpcRequest) -> Result<Value, Stri
                 let response_json: Value = response.json().await
                     .map_err(|e| {
                         println!("[DEBUG] Failed to parse HostBridge response JSON: {}", e);
-                        format!("Failed to parse response JSON: {}", e)
+                        GrpcError::internal(format!("Failed to parse response JSON: {}", e))
                     })?;
                 println!("[DEBUG] HostBridge request successful: service={}, method={}", 
                     grpc_request.service, grpc_request.method);
@@ -595,18 +538,63 @@ async fn forward_to_hostbridge(grpc_request: &GrpcRequest) -> Result<Value, Stri
             } else {
                 let error_msg = format!("HostBridge returned error status: {}", status);
                 println!("[DEBUG] {}", error_msg);
-                Err(error_msg)
+                Err(match status {
+                    reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::NOT_IMPLEMENTED => GrpcError::not_implemented(error_msg),
+                    reqwest::StatusCode::BAD_REQUEST | reqwest::StatusCode::UNPROCESSABLE_ENTITY => GrpcError::invalid_argument(error_msg),
+                    reqwest::StatusCode::REQUEST_TIMEOUT | reqwest::StatusCode::GATEWAY_TIMEOUT => GrpcError::timeout(error_msg),
+                    reqwest::StatusCode::SERVICE_UNAVAILABLE => GrpcError::unavailable(error_msg),
+                    _ => GrpcError::internal(error_msg),
+                })
             }
         }
         Err(e) => {
             let error_msg = format!("Failed to forward request to HostBridge: {}", e);
             println!("[DEBUG] {}", error_msg);
-            Err(error_msg)
+            Err(if e.is_timeout() {
+                GrpcError::timeout(error_msg)
+            } else {
+                GrpcError::unavailable(error_msg)
+            })
         }
     }
 }
 
+// 主窗口的 label，只有它关闭时才结束子进程并退出应用
+const MAIN_WINDOW: &str = "main";
+
+// 在后台线程中优雅地结束子进程后退出应用，避免在宽限期内阻塞事件循环
+fn shutdown_and_exit(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let state = app_handle.state::<SharedProcessManager>();
+        process_manager::shutdown::shutdown_gracefully(state.inner());
+        app_handle.exit(0);
+    });
+}
+
 fn main() {
+    // 命令行中指定的工作区
+    let workspace = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| single_instance::workspace::workspace_from_args(std::env::args(), &cwd));
+    
+    // 同一时间只运行一个实例，再次启动时把工作区转交给已运行的实例
+    let mut instance_lock = None;
+    if single_instance::enabled() {
+        let hand_off = HandOff { workspace: workspace.clone() };
+        match single_instance::acquire_or_forward(&single_instance::instance_dir(), &hand_off) {
+            Ok(Acquired::Primary(lock)) => instance_lock = Some(lock),
+            Ok(Acquired::Secondary) => {
+                println!("Cline Desktop is already running, workspace handed off to it");
+                return;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                eprintln!("[INSTANCE] Cline Desktop is already running: {}", e);
+                std::process::exit(1);
+            }
+            Err(e) => eprintln!("[INSTANCE] Failed to acquire single-instance lock: {}, continuing without it", e),
+        }
+    }
+    
     // 创建进程管理器
     let process_manager = create_process_manager();
     
@@ -614,46 +602,112 @@ fn main() {
     let cleanup_manager = process_manager.clone();
     ctrlc::set_handler(move || {
         println!("Received Ctrl+C, cleaning up child processes...");
-        if let Ok(mut manager) = cleanup_manager.lock() {
-            manager.kill_all();
-        }
+        process_manager::shutdown::shutdown_gracefully(&cleanup_manager);
         std::process::exit(0);
     }).expect("Error setting Ctrl-C handler");
     
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(process_manager) // 将进程管理器添加到Tauri状态中
+        .manage(hostbridge::WorkspaceState::new(workspace))
+        .manage(bridge::StreamRegistry::new())
         .invoke_handler(tauri::generate_handler![
             select_workspace,
+            open_workspace,
+            get_workspace,
             start_cline_core,
             start_node_server,
             start_node_server_sidecar,
             stop_all_processes,
+            list_processes,
+            stop_process,
+            restart_process,
+            tail_process_output,
+            read_process_log,
+            get_process_stats,
+            get_service_ports,
             test_grpc_connection,
+            list_checkpoints,
+            is_offline_mode,
             handle_webview_message
         ])
-        .setup(|app| {
+        .setup(move |app| {
             let app_handle = app.handle().clone();
+            
+            // 子进程输出写入应用日志目录，便于在崩溃后排查问题
+            match app.path().app_log_dir() {
+                Ok(log_dir) => {
+                    let state = app.state::<SharedProcessManager>();
+                    state.lock().unwrap().set_log_dir(log_dir.join("processes"));
+                }
+                Err(e) => eprintln!("[STARTUP] Failed to resolve app log directory: {}", e),
+            }
+            
+            let desktop_settings = settings::DesktopSettings::load(&app_handle);
+            
+            // 定期采样子进程的 CPU、内存和文件描述符，超过内存上限时重启
+            process_manager::monitor::start(
+                app_handle.clone(),
+                app.state::<SharedProcessManager>().inner().clone(),
+                process_manager::monitor::MonitorConfig::from_settings(&desktop_settings.monitoring),
+            );
+            
+            // 离线演示模式需要显式开启，开启后不启动 cline-core
+            let offline_state = offline::OfflineState::from_settings(&desktop_settings.offline);
+            let offline_enabled = offline_state.enabled();
+            if offline_enabled {
+                println!("[STARTUP] Offline mode enabled, cline.* requests are answered from fixtures");
+            }
+            app.manage(offline_state);
+            
+            // 接收之后启动的实例转交的工作区，并保持锁直到应用退出
+            if let Some(mut lock) = instance_lock {
+                let hand_off_handle = app_handle.clone();
+                lock.listen(move |hand_off| {
+                    single_instance::workspace::handle_hand_off(&hand_off_handle, hand_off);
+                })?;
+                app.manage(lock);
+            }
 
-            if let Some(window) = app.get_webview_window("main") {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
                 window.set_title("Cline Desktop").unwrap();
                 // 其他窗口自定义操作
             }
             
+            // 为本实例分配端口，避免多个实例之间互相冲突
+            // HostBridge 先绑定系统分配的端口，ProtoBus 端口交给 cline-core 绑定
+            let hostbridge_listener = hostbridge::bind_listener(0)?;
+            let ports = ServicePorts::new(
+                ports::pick_free_port()?,
+                hostbridge_listener.local_addr()?.port(),
+            );
+            println!("[STARTUP] ProtoBus port: {}, HostBridge port: {}", ports.protobus, ports.hostbridge);
+            // 所有 webview 请求共用一个客户端，连接在第一次请求时建立
+            app.manage(grpc_client::ClineGrpcClient::with_config(grpc_client::connection::ConnectionConfig {
+                endpoint: ports.protobus_endpoint(),
+                local_models: grpc_client::local_models::LocalModelDiscovery::from_settings(&desktop_settings.local_models),
+                desktop_files: Some(std::sync::Arc::new(native_files::TauriFileOps::new(app_handle.clone()))),
+                ..Default::default()
+            }));
+            app.manage(SharedServicePorts::new(ports));
+            
             // 首先启动 HostBridge 服务器（在 Rust 中）
             let hostbridge_handle = app_handle.clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = hostbridge::start_hostbridge_server(hostbridge_handle).await {
+                if let Err(e) = hostbridge::serve_hostbridge(hostbridge_handle, hostbridge_listener).await {
                     eprintln!("Failed to start HostBridge server: {}", e);
                 }
             });
             
             // 在应用启动时自动启动cline-core
+            // HostBridge 端口已经绑定，cline-core 的连接会在服务启动后被接受，无需等待
+            if offline_enabled {
+                return Ok(());
+            }
             tauri::async_runtime::spawn(async move {
-                // 等待 HostBridge 服务启动（1秒）
-                tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-                
                 // 获取进程管理器状态
                 let process_manager_state = app_handle.state::<SharedProcessManager>();
                 
@@ -666,32 +720,43 @@ fn main() {
             
             Ok(())
         })
+        .on_page_load(|webview, payload| {
+            // 页面重新加载后旧页面的订阅不再有接收者，全部取消
+            if payload.event() == tauri::webview::PageLoadEvent::Started {
+                let cancelled = webview.state::<bridge::StreamRegistry>().cancel_webview(webview.label());
+                if cancelled > 0 {
+                    println!("[DEBUG] Webview '{}' is loading, cancelled {} streams", webview.label(), cancelled);
+                }
+            }
+        })
         .on_window_event(|window, event| {
-            // 当窗口关闭时，确保所有子进程都被终止
+            // 主窗口关闭时，确保所有子进程都被终止
             match event {
                 tauri::WindowEvent::Destroyed => {
-                    println!("Window is being destroyed, killing all child processes...");
-                    let app_handle = window.app_handle();
-                    let state = app_handle.state::<SharedProcessManager>();
-                    
-                    // 直接调用非异步的 kill_all 方法
-                    if let Ok(mut manager) = state.lock() {
-                        manager.kill_all();
-                    };
+                    window.state::<bridge::StreamRegistry>().cancel_webview(window.label());
+                    if window.label() == MAIN_WINDOW {
+                        // 关闭流程只会执行一次，若已在 CloseRequested 中完成则直接退出
+                        println!("Main window is being destroyed, shutting down all child processes...");
+                        shutdown_and_exit(window.app_handle().clone());
+                    }
                 }
-                tauri::WindowEvent::CloseRequested { .. } => {
-                    println!("Window close requested, preparing to kill all child processes...");
-                    let app_handle = window.app_handle();
-                    let state = app_handle.state::<SharedProcessManager>();
-                    
-                    // 在窗口关闭请求时也清理进程
-                    if let Ok(mut manager) = state.lock() {
-                        manager.kill_all();
-                    };
+                tauri::WindowEvent::CloseRequested { api, .. } if window.label() == MAIN_WINDOW => {
+                    println!("Main window close requested, shutting down all child processes...");
+                    // 先保留窗口，在后台优雅地结束子进程，让 cline-core 有机会保存任务历史和检查点
+                    api.prevent_close();
+                    let _ = window.hide();
+                    shutdown_and_exit(window.app_handle().clone());
                 }
                 _ => {}
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // 最后一个窗口关闭时不立即退出，等子进程结束后再由 shutdown_and_exit 退出
+            if let tauri::RunEvent::ExitRequested { code: None, api, .. } = event {
+                api.prevent_exit();
+                shutdown_and_exit(app_handle.clone());
+            }
+        });
 }
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::grpc_client::{
+    services::{file_service::IMAGE_EXTENSIONS, DesktopFileOps},
+    types::GrpcResult,
+    GrpcError,
+};
+use crate::hostbridge::WorkspaceState;
+
+/// FileService 中属于桌面端的操作，直接通过 Tauri 插件完成
+#[derive(Clone)]
+pub struct TauriFileOps {
+    app_handle: AppHandle,
+    // 用户在对话框中选择过的文件（规范化后的路径）
+    selected: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl TauriFileOps {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle, selected: Arc::new(Mutex::new(HashSet::new())) }
+    }
+}
+
+impl std::fmt::Debug for TauriFileOps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TauriFileOps").finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl DesktopFileOps for TauriFileOps {
+    fn workspace_root(&self) -> Option<PathBuf> {
+        self.app_handle.state::<WorkspaceState>().get().map(PathBuf::from)
+    }
+
+    async fn open_path(&self, path: &Path) -> GrpcResult<()> {
+        self.app_handle
+            .opener()
+            .open_path(path.to_string_lossy(), None::<&str>)
+            .map_err(|e| GrpcError::internal(format!("Failed to open {}: {}", path.display(), e)))?;
+        Ok(())
+    }
+
+    async fn open_url(&self, url: &str) -> GrpcResult<()> {
+        self.app_handle
+            .opener()
+            .open_url(url, None::<&str>)
+            .map_err(|e| GrpcError::internal(format!("Failed to open {}: {}", url, e)))?;
+        Ok(())
+    }
+
+    async fn pick_files(&self, images_only: bool) -> GrpcResult<Vec<PathBuf>> {
+        let mut dialog = self.app_handle.dialog().file();
+        if images_only {
+            dialog = dialog.add_filter("Images", &IMAGE_EXTENSIONS);
+        }
+
+        // 对话框通过回调返回结果
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        dialog.pick_files(move |files| {
+            let _ = tx.send(files);
+        });
+        let files = rx.await.map_err(|_| GrpcError::internal("File dialog closed without a result"))?;
+
+        let paths: Vec<PathBuf> = files
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|file| file.into_path().ok())
+            .collect();
+        self.selected
+            .lock()
+            .unwrap()
+            .extend(paths.iter().filter_map(|path| path.canonicalize().ok()));
+        Ok(paths)
+    }
+
+    fn is_user_selected(&self, path: &Path) -> bool {
+        self.selected.lock().unwrap().contains(path)
+    }
+
+    async fn write_clipboard(&self, text: &str) -> GrpcResult<()> {
+        self.app_handle
+            .clipboard()
+            .write_text(text.to_string())
+            .map_err(|e| GrpcError::internal(format!("Failed to write clipboard: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde_json::Value;
+
+use crate::grpc_client::GrpcError;
+use crate::settings::OfflineSettings;
+
+#[cfg(test)]
+mod tests;
+
+// 内置的离线数据，键为 "<service>/<method>"
+const BUILTIN_FIXTURES: &str = include_str!("fixtures.json");
+
+// 离线演示模式下代替 cline-core 响应 cline.* 请求
+#[derive(Debug, Clone, Default)]
+pub struct OfflineResponder {
+    fixtures: HashMap<String, Value>,
+}
+
+impl OfflineResponder {
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_FIXTURES).expect("built-in offline fixtures must be valid JSON")
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let fixtures: HashMap<String, Value> = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        Ok(Self {
+            fixtures: fixtures.into_iter().map(|(key, value)| (key, normalize(value))).collect(),
+        })
+    }
+
+    // 内置数据加上 dir 中的 *.json，同名键以目录中的数据为准
+    pub fn load(dir: Option<&Path>) -> Result<Self, String> {
+        let mut responder = Self::builtin();
+        let dir = match dir {
+            Some(dir) => dir,
+            None => return Ok(responder),
+        };
+
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read fixtures directory {}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let content = std::fs::read_to_string(&file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            let overrides = Self::from_json(&content).map_err(|e| format!("Invalid fixture {}: {}", file.display(), e))?;
+            responder.fixtures.extend(overrides.fixtures);
+        }
+        Ok(responder)
+    }
+
+    pub fn fixture(&self, service: &str, method: &str) -> Option<&Value> {
+        self.fixtures.get(&format!("{}/{}", service, method))
+    }
+
+    pub fn respond(&self, service: &str, method: &str) -> Result<Value, GrpcError> {
+        self.fixture(service, method).cloned().ok_or_else(|| {
+            GrpcError::not_implemented(format!("{}.{} is not available in offline mode", service, method))
+        })
+    }
+}
+
+// State 消息的 stateJson 是 JSON 字符串，数据文件中允许直接写成对象
+fn normalize(mut value: Value) -> Value {
+    if let Some(state) = value.get_mut("stateJson") {
+        if !state.is_string() {
+            *state = Value::String(state.to_string());
+        }
+    }
+    value
+}
+
+// 离线模式状态；未启用时为 None
+pub struct OfflineState {
+    responder: Option<OfflineResponder>,
+}
+
+impl OfflineState {
+    pub fn from_settings(settings: &OfflineSettings) -> Self {
+        if !settings.enabled {
+            return Self { responder: None };
+        }
+
+        let responder = OfflineResponder::load(settings.fixtures_dir.as_deref()).unwrap_or_else(|e| {
+            eprintln!("[OFFLINE] {}, using built-in fixtures only", e);
+            OfflineResponder::builtin()
+        });
+        Self { responder: Some(responder) }
+    }
+
+    pub fn responder(&self) -> Option<&OfflineResponder> {
+        self.responder.as_ref()
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.responder.is_some()
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod offline_tests {
+    use crate::grpc_client::ErrorCode;
+    use crate::offline::{OfflineResponder, OfflineState};
+    use crate::settings::{DesktopSettings, OfflineSettings};
+
+    #[test]
+    fn test_builtin_state_fixture_is_a_state_message() {
+        let responder = OfflineResponder::builtin();
+        let state = responder.respond("cline.StateService", "getLatestState").unwrap();
+
+        // stateJson 与 cline-core 返回的格式一致，是 JSON 字符串
+        let state_json: serde_json::Value = serde_json::from_str(state["stateJson"].as_str().unwrap()).unwrap();
+        assert!(state_json["clineMessages"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_fixture_is_not_implemented() {
+        let responder = OfflineResponder::builtin();
+        let error = responder.respond("cline.TaskService", "newTask").unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::NotImplemented);
+        assert!(error.message.contains("offline mode"));
+    }
+
+    #[test]
+    fn test_fixtures_dir_overrides_builtin() {
+        let dir = std::env::temp_dir().join(format!("cline-desktop-fixtures-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("task.json"),
+            r#"{
+                "cline.McpService/getLatestMcpServers": { "mcpServers": [{ "name": "demo" }] },
+                "cline.TaskService/getTaskHistory": { "tasks": [] }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let responder = OfflineResponder::load(Some(&dir)).unwrap();
+        assert_eq!(
+            responder.respond("cline.McpService", "getLatestMcpServers").unwrap()["mcpServers"][0]["name"],
+            "demo"
+        );
+        assert!(responder.respond("cline.TaskService", "getTaskHistory").is_ok());
+        assert!(responder.respond("cline.StateService", "getLatestState").is_ok());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_offline_mode_is_opt_in() {
+        assert!(!OfflineState::from_settings(&OfflineSettings::default()).enabled());
+
+        let mut settings = DesktopSettings::default();
+        settings.apply_env_overrides(|key| if key == "CLINE_DESKTOP_OFFLINE" { Some("1".to_string()) } else { None });
+        assert!(OfflineState::from_settings(&settings.offline).enabled());
+    }
+}
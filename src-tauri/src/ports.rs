@@ -0,0 +1,64 @@
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::RwLock;
+use serde::Serialize;
+
+// 本地服务只监听回环地址
+pub const LOOPBACK_HOST: &str = "127.0.0.1";
+
+// 传给 cline-core 的地址环境变量
+pub const PROTOBUS_ADDRESS_ENV: &str = "PROTOBUS_ADDRESS";
+pub const HOST_BRIDGE_ADDRESS_ENV: &str = "HOST_BRIDGE_ADDRESS";
+
+// 本实例的 ProtoBus（cline-core）和 HostBridge 端口，启动时分配
+// HostBridge 端口由本进程持有，之后保持不变；ProtoBus 端口被抢占时会重新分配
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ServicePorts {
+    pub protobus: u16,
+    pub hostbridge: u16,
+}
+
+impl ServicePorts {
+    pub fn new(protobus: u16, hostbridge: u16) -> Self {
+        Self { protobus, hostbridge }
+    }
+
+    // host:port 形式，供 cline-core 使用
+    pub fn protobus_address(&self) -> String {
+        format!("{}:{}", LOOPBACK_HOST, self.protobus)
+    }
+
+    // 换用新的 ProtoBus 端口，HostBridge 端口不变
+    pub fn with_protobus(self, protobus: u16) -> Self {
+        Self { protobus, ..self }
+    }
+
+    pub fn hostbridge_address(&self) -> String {
+        format!("{}:{}", LOOPBACK_HOST, self.hostbridge)
+    }
+
+    // http://host:port 形式，供 gRPC 客户端使用
+    pub fn protobus_endpoint(&self) -> String {
+        format!("http://{}", self.protobus_address())
+    }
+
+    pub fn hostbridge_endpoint(&self) -> String {
+        format!("http://{}", self.hostbridge_address())
+    }
+}
+
+// 保存在 Tauri 状态中的端口，读取时复制一份
+pub type SharedServicePorts = RwLock<ServicePorts>;
+
+// 向系统申请一个当前空闲的本地端口
+// 端口在返回前即被释放，子进程绑定之前可能被其他进程抢占，
+// 调用方需要在子进程报告端口被占用时换一个端口重试（见 is_address_in_use）
+pub fn pick_free_port() -> std::io::Result<u16> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+// 判断子进程的一行输出是否表示监听端口已被占用
+// Node 报告为 "EADDRINUSE"，gRPC 的绑定错误中包含 "address already in use"
+pub fn is_address_in_use(line: &str) -> bool {
+    line.contains("EADDRINUSE") || line.to_ascii_lowercase().contains("address already in use")
+}
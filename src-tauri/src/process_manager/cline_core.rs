@@ -0,0 +1,475 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent, TerminatedPayload};
+
+use crate::grpc_client::connection::DEFAULT_ENDPOINT;
+use crate::grpc_client::ClineGrpcClient;
+use crate::grpc_client::health::wait_until_serving;
+use crate::ports::{
+    is_address_in_use, pick_free_port, ServicePorts, SharedServicePorts, HOST_BRIDGE_ADDRESS_ENV,
+    PROTOBUS_ADDRESS_ENV,
+};
+use crate::settings::{ClineCoreSettings, DesktopSettings};
+use super::{
+    OutputStream, ProcessKind, ProcessSpec, RestartDecision, RestartTracker, SharedProcessManager,
+    CLINE_CORE_ID,
+};
+
+// cline-core 即将重启时发送给前端的事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct ClineCoreRestarting {
+    pub attempt: usize,
+    pub max_restarts: usize,
+    pub delay_ms: u64,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+// 超过重启上限、放弃重启时发送给前端的事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct ClineCoreGaveUp {
+    pub restarts: usize,
+    pub window_secs: u64,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+// cline-core 未能就绪时发送给前端的事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct ClineCoreStartupFailed {
+    pub reason: String,
+    pub stderr: Vec<String>,
+}
+
+// 通过 grpc.health.v1 探测 ProtoBus 是否就绪的配置
+#[derive(Debug, Clone)]
+pub struct ReadinessConfig {
+    pub endpoint: String,
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl ReadinessConfig {
+    pub fn for_endpoint(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+// 启动失败事件中附带的 stderr 行数
+const STARTUP_FAILURE_STDERR_LINES: usize = 50;
+
+// cline-core 的入口脚本
+pub const CLINE_CORE_ENTRY: &str = "cline-core.js";
+// 从源码树运行时 cline-core 的位置（相对于 src-tauri）
+const DEV_TREE_DIR: &str = "../cline/dist-standalone";
+
+// 按优先级查找 cline-core 所在目录：
+// 显式配置（环境变量或设置文件）> Tauri 资源目录 > 源码树
+pub fn resolve_cline_core_dir(explicit: Option<&Path>, fallbacks: &[PathBuf]) -> Result<PathBuf, String> {
+    if let Some(dir) = explicit {
+        // 显式配置错误时直接报错，避免悄悄使用其它版本的 cline-core
+        return if dir.join(CLINE_CORE_ENTRY).is_file() {
+            Ok(dir.to_path_buf())
+        } else {
+            Err(format!("{} not found in configured cline-core directory {}", CLINE_CORE_ENTRY, dir.display()))
+        };
+    }
+
+    fallbacks
+        .iter()
+        .find(|dir| dir.join(CLINE_CORE_ENTRY).is_file())
+        .cloned()
+        .ok_or_else(|| format!(
+            "Could not locate {}; searched: {}",
+            CLINE_CORE_ENTRY,
+            fallbacks.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+        ))
+}
+
+// 未显式配置时依次尝试的目录
+fn fallback_dirs(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(resource_dir) = app_handle.path().resource_dir() {
+        // tauri.conf.json 中的 "../cline/dist-standalone" 打包后位于 _up_ 目录下
+        dirs.push(resource_dir.join("_up_").join("cline").join("dist-standalone"));
+        dirs.push(resource_dir.join("dist-standalone"));
+    }
+    dirs.push(PathBuf::from(DEV_TREE_DIR));
+    dirs
+}
+
+// 根据设置和本实例的端口构造 cline-core 的启动配置
+pub fn build_cline_core_spec(settings: &ClineCoreSettings, dir: &Path, ports: &ServicePorts) -> ProcessSpec {
+    // node [node 参数] cline-core.js [cline-core 参数]
+    let mut args = settings.node_args.clone();
+    args.push(CLINE_CORE_ENTRY.to_string());
+    args.extend(settings.args.iter().cloned());
+
+    // 端口由本实例分配，不允许被设置中的环境变量覆盖
+    let mut env = settings.env.clone();
+    env.insert(PROTOBUS_ADDRESS_ENV.to_string(), ports.protobus_address());
+    env.insert(HOST_BRIDGE_ADDRESS_ENV.to_string(), ports.hostbridge_address());
+
+    ProcessSpec {
+        name: "cline-core".to_string(),
+        kind: ProcessKind::Command,
+        program: settings.node_path.clone().unwrap_or_else(|| "node".to_string()),
+        args,
+        cwd: Some(dir.to_string_lossy().to_string()),
+        port: Some(ports.protobus),
+        env,
+    }
+}
+
+// 读取当前设置并解析出 cline-core 的启动配置
+pub fn cline_core_spec(app_handle: &AppHandle) -> Result<ProcessSpec, String> {
+    let settings = DesktopSettings::load(app_handle).cline_core;
+    let dir = resolve_cline_core_dir(settings.dist_dir.as_deref(), &fallback_dirs(app_handle))?;
+    let ports = service_ports(app_handle)?;
+    println!("Using cline-core from {} (ProtoBus port {}, HostBridge port {})", dir.display(), ports.protobus, ports.hostbridge);
+    Ok(build_cline_core_spec(&settings, &dir, &ports))
+}
+
+// 启动时分配的端口，保存在 Tauri 状态中
+fn service_ports(app_handle: &AppHandle) -> Result<ServicePorts, String> {
+    app_handle
+        .try_state::<SharedServicePorts>()
+        .map(|ports| *ports.read().unwrap())
+        .ok_or_else(|| "Service ports have not been allocated".to_string())
+}
+
+// ProtoBus 端口在分配后被其他进程抢占时，换一个新端口，并让 gRPC 客户端连接新地址
+fn reassign_protobus_port(app_handle: &AppHandle) -> Result<ServicePorts, String> {
+    let state = app_handle
+        .try_state::<SharedServicePorts>()
+        .ok_or_else(|| "Service ports have not been allocated".to_string())?;
+    let port = pick_free_port().map_err(|e| format!("Failed to allocate a ProtoBus port: {}", e))?;
+    let ports = {
+        let mut ports = state.write().unwrap();
+        *ports = ports.with_protobus(port);
+        *ports
+    };
+    if let Some(client) = app_handle.try_state::<ClineGrpcClient>() {
+        client.set_endpoint(&ports.protobus_endpoint());
+    }
+    Ok(ports)
+}
+
+// 启动一个 cline-core 进程，同时返回所用的启动配置
+pub fn spawn_cline_core(app_handle: &AppHandle) -> Result<(Receiver<CommandEvent>, CommandChild, ProcessSpec), String> {
+    let spec = cline_core_spec(app_handle)?;
+    let (rx, child) = spec
+        .spawn(app_handle)
+        .map_err(|e| format!("Failed to spawn cline-core process: {}", e))?;
+    Ok((rx, child, spec))
+}
+
+// 启动 cline-core 并交给监督任务管理，返回新进程的 pid
+pub fn start_supervised(app_handle: &AppHandle, process_manager: &SharedProcessManager) -> Result<u32, String> {
+    let (rx, child, spec) = spawn_cline_core(app_handle)?;
+    let pid = child.pid();
+
+    // 将子进程添加到进程管理器中，并记录当前的监督周期
+    let epoch = {
+        let mut manager = process_manager.lock().unwrap();
+        manager.register(CLINE_CORE_ID, spec, child);
+        manager.supervision_epoch()
+    };
+
+    // 处理cline-core的输出，并在意外退出时自动重启
+    supervise_cline_core(app_handle.clone(), process_manager.clone(), rx, pid, epoch);
+
+    Ok(pid)
+}
+
+// 在后台监督 cline-core：意外退出时按退避策略重启，超过上限后放弃
+pub fn supervise_cline_core(
+    app_handle: AppHandle,
+    process_manager: SharedProcessManager,
+    rx: Receiver<CommandEvent>,
+    pid: u32,
+    epoch: u64,
+) {
+    tauri::async_runtime::spawn(async move {
+        let policy = match process_manager.lock() {
+            Ok(manager) => manager.restart_policy().clone(),
+            Err(_) => return,
+        };
+        let mut tracker = RestartTracker::new(policy);
+        let mut rx = rx;
+        let mut pid = pid;
+        let mut started_at = Instant::now();
+
+        loop {
+            // 每次启动都按当前端口探测，端口可能在上一次重启前被重新分配
+            let readiness = service_ports(&app_handle)
+                .map(|ports| ReadinessConfig::for_endpoint(ports.protobus_endpoint()))
+                .unwrap_or_default();
+            let (status, port_taken) = run_until_exit(&app_handle, &process_manager, &readiness, rx).await;
+            let (exit_code, signal) = status
+                .as_ref()
+                .map(|s| (s.code, s.signal))
+                .unwrap_or((None, None));
+
+            // 进程已退出，记录退出状态并清除旧句柄
+            if let Ok(mut manager) = process_manager.lock() {
+                manager.mark_exited(CLINE_CORE_ID, pid, exit_code, signal);
+            }
+            let mut uptime = started_at.elapsed();
+
+            // 分配的端口在 cline-core 绑定之前被其他进程占用，下一次启动换一个端口
+            if port_taken {
+                match reassign_protobus_port(&app_handle) {
+                    Ok(ports) => println!("[SUPERVISOR] ProtoBus port was taken, switching to port {}", ports.protobus),
+                    Err(e) => eprintln!("[SUPERVISOR] {}", e),
+                }
+            }
+
+            // 在退避与重新启动之间循环，直到成功启动或放弃
+            let next = loop {
+                if !should_restart(&process_manager, epoch) {
+                    println!("[SUPERVISOR] cline-core was stopped intentionally, not restarting");
+                    break None;
+                }
+
+                match tracker.on_exit(Instant::now(), uptime) {
+                    RestartDecision::Restart { attempt, delay } => {
+                        let max_restarts = tracker.policy().retry_config.max_retries;
+                        println!(
+                            "[SUPERVISOR] cline-core exited unexpectedly (code: {:?}, signal: {:?}), restarting in {}ms (attempt {}/{})",
+                            exit_code, signal, delay.as_millis(), attempt, max_restarts
+                        );
+                        emit_to_main(&app_handle, "cline-core-restarting", ClineCoreRestarting {
+                            attempt,
+                            max_restarts,
+                            delay_ms: delay.as_millis() as u64,
+                            exit_code,
+                            signal,
+                        });
+
+                        tokio::time::sleep(delay).await;
+
+                        // 等待期间可能已被主动停止
+                        if !should_restart(&process_manager, epoch) {
+                            println!("[SUPERVISOR] cline-core was stopped during backoff, not restarting");
+                            break None;
+                        }
+
+                        match spawn_cline_core(&app_handle) {
+                            Ok(spawned) => break Some(spawned),
+                            Err(e) => {
+                                // 启动失败同样计入重启次数
+                                eprintln!("[SUPERVISOR] {}", e);
+                                uptime = Duration::ZERO;
+                            }
+                        }
+                    }
+                    RestartDecision::GiveUp { restarts } => {
+                        let window_secs = tracker.policy().restart_window.as_secs();
+                        eprintln!(
+                            "[SUPERVISOR] cline-core restarted {} times within {}s, giving up",
+                            restarts, window_secs
+                        );
+                        emit_to_main(&app_handle, "cline-core-gave-up", ClineCoreGaveUp {
+                            restarts,
+                            window_secs,
+                            exit_code,
+                            signal,
+                        });
+                        break None;
+                    }
+                }
+            };
+
+            match next {
+                Some((new_rx, child, spec)) => {
+                    let new_pid = child.pid();
+                    match process_manager.lock() {
+                        Ok(mut manager) if manager.should_restart(epoch) => {
+                            manager.register(CLINE_CORE_ID, spec, child)
+                        }
+                        _ => {
+                            // 注册之前恰好被主动停止，直接结束新进程
+                            let _ = child.kill();
+                            break;
+                        }
+                    }
+                    println!("[SUPERVISOR] cline-core restarted (pid {})", new_pid);
+                    rx = new_rx;
+                    pid = new_pid;
+                    started_at = Instant::now();
+                }
+                None => break,
+            }
+        }
+    });
+}
+
+// 转发进程输出的同时探测 ProtoBus 是否就绪，直到进程退出
+// 返回退出状态，以及进程是否因为端口被占用而未能就绪
+async fn run_until_exit(
+    app_handle: &AppHandle,
+    process_manager: &SharedProcessManager,
+    readiness: &ReadinessConfig,
+    rx: Receiver<CommandEvent>,
+) -> (Option<TerminatedPayload>, bool) {
+    let port_taken = AtomicBool::new(false);
+    let output = forward_cline_core_output(app_handle, process_manager, rx, &port_taken);
+    let probe = wait_until_serving(&readiness.endpoint, readiness.timeout, readiness.poll_interval);
+    tokio::pin!(output);
+    tokio::pin!(probe);
+
+    let mut probing = true;
+    let mut ready = false;
+    let status = loop {
+        tokio::select! {
+            status = &mut output => break status,
+            result = &mut probe, if probing => {
+                probing = false;
+                match result {
+                    Ok(elapsed) => {
+                        ready = true;
+                        println!("[DEBUG] cline-core ProtoBus is serving after {}ms, emitting cline-core-ready", elapsed.as_millis());
+                        emit_to_main(app_handle, "cline-core-ready", ());
+                    }
+                    Err(e) => {
+                        eprintln!("[DEBUG] cline-core failed to become ready: {}", e);
+                        emit_startup_failed(app_handle, process_manager, e.to_string());
+                        // 端口被占用但进程没有退出时结束它，交给监督任务换端口重启
+                        if port_taken.load(Ordering::SeqCst) {
+                            kill_for_restart(process_manager);
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    // 进程在就绪之前就退出了
+    if probing {
+        emit_startup_failed(
+            app_handle,
+            process_manager,
+            format!("cline-core exited before becoming ready: {:?}", status),
+        );
+    }
+
+    (status, !ready && port_taken.load(Ordering::SeqCst))
+}
+
+// 结束当前的 cline-core，但不停止监督，监督任务会把它当作崩溃并重启
+fn kill_for_restart(process_manager: &SharedProcessManager) {
+    let child = process_manager
+        .lock()
+        .ok()
+        .and_then(|mut manager| manager.take_child(CLINE_CORE_ID, false).ok().flatten());
+    if let Some(child) = child {
+        if let Err(e) = child.kill() {
+            eprintln!("[SUPERVISOR] Failed to kill cline-core: {}", e);
+        }
+    }
+}
+
+fn emit_startup_failed(app_handle: &AppHandle, process_manager: &SharedProcessManager, reason: String) {
+    let stderr = process_manager
+        .lock()
+        .map(|manager| manager.recent_stderr(CLINE_CORE_ID, STARTUP_FAILURE_STDERR_LINES))
+        .unwrap_or_default();
+    emit_to_main(app_handle, "cline-core-startup-failed", ClineCoreStartupFailed { reason, stderr });
+}
+
+fn should_restart(process_manager: &SharedProcessManager, epoch: u64) -> bool {
+    process_manager
+        .lock()
+        .map(|manager| manager.should_restart(epoch))
+        .unwrap_or(false)
+}
+
+pub(crate) fn emit_to_main<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.emit(event, payload) {
+            eprintln!("Failed to emit {} event: {}", event, e);
+        }
+    }
+}
+
+fn record_output(process_manager: &SharedProcessManager, stream: OutputStream, line: &str) {
+    if let Ok(mut manager) = process_manager.lock() {
+        manager.record_output(CLINE_CORE_ID, stream, line.trim_end().to_string());
+    }
+}
+
+// 处理 cline-core 的输出，直到进程退出；返回退出状态
+// stderr 报告端口被占用时设置 port_taken
+async fn forward_cline_core_output(
+    app_handle: &AppHandle,
+    process_manager: &SharedProcessManager,
+    mut rx: Receiver<CommandEvent>,
+    port_taken: &AtomicBool,
+) -> Option<TerminatedPayload> {
+    while let Some(event) = rx.recv().await {
+        let window = app_handle.get_webview_window("main");
+        match event {
+            CommandEvent::Stdout(line) => {
+                let line_str = String::from_utf8_lossy(&line);
+                println!("cline-core stdout: {}", line_str);
+                record_output(process_manager, OutputStream::Stdout, &line_str);
+
+                if let Some(window) = window {
+                    window
+                        .emit("cline-stdout", line_str.to_string())
+                        .expect("failed to emit event");
+                }
+            }
+            CommandEvent::Stderr(line) => {
+                eprintln!("cline-core stderr: {}", String::from_utf8_lossy(&line));
+                record_output(process_manager, OutputStream::Stderr, &String::from_utf8_lossy(&line));
+                if is_address_in_use(&String::from_utf8_lossy(&line)) {
+                    port_taken.store(true, Ordering::SeqCst);
+                }
+                if let Some(window) = window {
+                    window
+                        .emit("cline-stderr", String::from_utf8_lossy(&line).to_string())
+                        .expect("failed to emit event");
+                }
+            }
+            CommandEvent::Error(err) => {
+                eprintln!("cline-core error: {}", err);
+                if let Some(window) = window {
+                    window
+                        .emit("cline-error", err.to_string())
+                        .expect("failed to emit event");
+                }
+            }
+            CommandEvent::Terminated(status) => {
+                println!("cline-core terminated with status: {:?}", status);
+                if let Some(window) = window {
+                    window
+                        .emit("cline-terminated", status.clone())
+                        .expect("failed to emit event");
+                }
+                return Some(status);
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
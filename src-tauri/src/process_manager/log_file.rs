@@ -0,0 +1,263 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+
+use super::{OutputLine, OutputStream};
+
+// 日志文件的轮转策略
+#[derive(Debug, Clone)]
+pub struct LogRotation {
+    // 单个日志文件的最大字节数，超过后轮转
+    pub max_bytes: u64,
+    // 保留的历史日志文件数量（<id>.log.1 ... <id>.log.N）
+    pub max_files: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: 5 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+// 读取日志时的过滤条件，时间均为毫秒时间戳
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    // 只返回最后 n 行
+    pub lines: Option<usize>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+impl LogQuery {
+    fn matches(&self, line: &OutputLine) -> bool {
+        self.since.map_or(true, |since| line.timestamp >= since)
+            && self.until.map_or(true, |until| line.timestamp <= until)
+    }
+}
+
+enum LogCommand {
+    Append(OutputLine),
+    // 写入之前的所有记录并落盘后回复
+    Flush(Sender<std::io::Result<()>>),
+}
+
+// 单个进程的日志文件，按大小轮转
+// 写入在后台线程中完成，记录输出时不会在持有进程管理器锁的情况下等待磁盘
+pub struct ProcessLog {
+    sender: Sender<LogCommand>,
+}
+
+impl ProcessLog {
+    pub fn open(dir: &Path, id: &str, rotation: LogRotation) -> std::io::Result<Self> {
+        let writer = LogWriter::open(dir, id, rotation)?;
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(format!("log-{}", id))
+            .spawn(move || writer.run(receiver))?;
+        Ok(Self { sender })
+    }
+
+    // 交给后台线程写入；只有写入线程已因错误退出时才返回错误
+    pub fn append(&mut self, line: &OutputLine) -> std::io::Result<()> {
+        self.sender
+            .send(LogCommand::Append(line.clone()))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Log writer has stopped"))
+    }
+
+    // 等待之前追加的记录全部写入磁盘
+    pub fn flush(&self) -> std::io::Result<()> {
+        let (reply, done) = mpsc::channel();
+        let stopped = || std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Log writer has stopped");
+        self.sender.send(LogCommand::Flush(reply)).map_err(|_| stopped())?;
+        done.recv().map_err(|_| stopped())?
+    }
+}
+
+struct LogWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+    size: u64,
+    rotation: LogRotation,
+}
+
+impl LogWriter {
+    fn open(dir: &Path, id: &str, rotation: LogRotation) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = log_path(dir, id);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file: BufWriter::new(file), size, rotation })
+    }
+
+    // 没有待写入的记录时立即落盘，保证进程崩溃前的输出不会丢失
+    fn run(mut self, receiver: Receiver<LogCommand>) {
+        let mut next = receiver.recv().ok();
+        while let Some(command) = next {
+            let result = match command {
+                LogCommand::Append(line) => self.append(&line),
+                LogCommand::Flush(reply) => {
+                    let _ = reply.send(self.file.flush());
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to write log {}: {}", self.path.display(), e);
+                return;
+            }
+
+            next = match receiver.try_recv() {
+                Ok(command) => Some(command),
+                Err(TryRecvError::Empty) => {
+                    let _ = self.file.flush();
+                    receiver.recv().ok()
+                }
+                Err(TryRecvError::Disconnected) => None,
+            };
+        }
+        let _ = self.file.flush();
+    }
+
+    fn append(&mut self, line: &OutputLine) -> std::io::Result<()> {
+        let entry = format_line(line);
+        if self.size > 0 && self.size + entry.len() as u64 > self.rotation.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(entry.as_bytes())?;
+        self.size += entry.len() as u64;
+        Ok(())
+    }
+
+    // <id>.log -> <id>.log.1 -> ... -> <id>.log.N，最旧的文件被删除
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.rotation.max_files == 0 {
+            self.file.get_ref().set_len(0)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        let _ = fs::remove_file(rotated_path(&self.path, self.rotation.max_files));
+        for index in (1..self.rotation.max_files).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        self.file = BufWriter::new(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+// 进程 id 可能由前端传入，只保留可安全用于文件名的字符
+pub fn log_path(dir: &Path, id: &str) -> PathBuf {
+    let name: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    dir.join(format!("{}.log", name))
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+// 日志行格式：<RFC 3339 时间> [<stream>] <内容>
+pub fn format_line(line: &OutputLine) -> String {
+    let timestamp = Utc
+        .timestamp_millis_opt(line.timestamp)
+        .single()
+        .unwrap_or_else(Utc::now)
+        .to_rfc3339_opts(SecondsFormat::Millis, true);
+    format!("{} [{}] {}\n", timestamp, stream_tag(line.stream), line.line)
+}
+
+pub fn parse_line(entry: &str) -> Option<OutputLine> {
+    let (timestamp, rest) = entry.split_once(' ')?;
+    let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?.timestamp_millis();
+    let rest = rest.strip_prefix('[')?;
+    let (tag, line) = rest.split_once("] ")
+        .or_else(|| rest.strip_suffix(']').map(|tag| (tag, "")))?;
+    let stream = match tag {
+        "stdout" => OutputStream::Stdout,
+        "stderr" => OutputStream::Stderr,
+        "system" => OutputStream::System,
+        _ => return None,
+    };
+    Some(OutputLine {
+        timestamp,
+        stream,
+        line: line.to_string(),
+    })
+}
+
+fn stream_tag(stream: OutputStream) -> &'static str {
+    match stream {
+        OutputStream::Stdout => "stdout",
+        OutputStream::Stderr => "stderr",
+        OutputStream::System => "system",
+    }
+}
+
+// 从最新的文件开始读取，取够所需的行数或早于 since 之后就不再读取更旧的文件
+// 一条记录不会跨越两个文件，轮转只发生在记录之间
+pub fn read_log(dir: &Path, id: &str, rotation: &LogRotation, query: &LogQuery) -> std::io::Result<Vec<OutputLine>> {
+    let path = log_path(dir, id);
+    let files = std::iter::once(path.clone())
+        .chain((1..=rotation.max_files).map(|index| rotated_path(&path, index)));
+
+    let mut newest_first: Vec<Vec<OutputLine>> = Vec::new();
+    let mut matched = 0;
+    for file in files {
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut entries = parse_entries(&content);
+        let older_than_since = query
+            .since
+            .zip(entries.first())
+            .map_or(false, |(since, first)| first.timestamp < since);
+
+        entries.retain(|line| query.matches(line));
+        matched += entries.len();
+        newest_first.push(entries);
+        if older_than_since || query.lines.map_or(false, |lines| matched >= lines) {
+            break;
+        }
+    }
+
+    let mut entries: Vec<OutputLine> = newest_first.into_iter().rev().flatten().collect();
+    if let Some(lines) = query.lines {
+        let skip = entries.len().saturating_sub(lines);
+        entries.drain(..skip);
+    }
+    Ok(entries)
+}
+
+fn parse_entries(content: &str) -> Vec<OutputLine> {
+    let mut entries: Vec<OutputLine> = Vec::new();
+    for entry in content.lines() {
+        match parse_line(entry) {
+            Some(line) => entries.push(line),
+            // 输出中包含换行时，后续行属于上一条记录
+            None => {
+                if let Some(last) = entries.last_mut() {
+                    last.line.push('\n');
+                    last.line.push_str(entry);
+                }
+            }
+        }
+    }
+    entries
+}
@@ -0,0 +1,220 @@
+pub mod cline_core;
+pub mod log_file;
+pub mod monitor;
+pub mod node_server;
+pub mod process;
+pub mod shutdown;
+pub mod stats;
+pub mod supervisor;
+pub mod tree;
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri_plugin_shell::process::CommandChild;
+
+pub use log_file::{LogQuery, LogRotation, ProcessLog};
+pub use process::{ManagedProcess, OutputLine, OutputStream, ProcessInfo, ProcessKind, ProcessSpec};
+pub use shutdown::{ShutdownConfig, ShutdownCoordinator};
+pub use stats::ProcessStats;
+pub use supervisor::{RestartDecision, RestartPolicy, RestartTracker};
+
+// 受监督的 cline-core 进程在管理器中的 id
+pub const CLINE_CORE_ID: &str = "cline-core";
+
+// 全局进程管理器，用于跟踪所有子进程
+pub struct ProcessManager {
+    processes: HashMap<String, ManagedProcess>,
+    // 每次主动停止 cline-core 时递增，监督任务据此区分主动停止与意外崩溃
+    supervision_epoch: u64,
+    restart_policy: RestartPolicy,
+    shutdown_config: ShutdownConfig,
+    shutdown: Arc<ShutdownCoordinator>,
+    // 进程日志目录，未设置时只在内存中保留输出
+    log_dir: Option<PathBuf>,
+    log_rotation: LogRotation,
+    // 最近一次资源采样结果
+    stats: Vec<ProcessStats>,
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self {
+            processes: HashMap::new(),
+            supervision_epoch: 0,
+            restart_policy: RestartPolicy::default(),
+            shutdown_config: ShutdownConfig::default(),
+            shutdown: Arc::new(ShutdownCoordinator::new()),
+            log_dir: None,
+            log_rotation: LogRotation::default(),
+            stats: Vec::new(),
+        }
+    }
+
+    // 设置日志目录，之后登记的进程的输出会写入 <dir>/<id>.log
+    pub fn set_log_dir(&mut self, dir: PathBuf) {
+        println!("Process logs will be written to {}", dir.display());
+        self.log_dir = Some(dir);
+    }
+
+    // 以 id 登记进程；同一 id 已存在时视为重启，替换旧进程
+    pub fn register(&mut self, id: &str, spec: ProcessSpec, child: CommandChild) {
+        println!("Registering process '{}' (pid {}): {}", id, child.pid(), spec.command_line());
+        let started = format!("Process started (pid {}): {}", child.pid(), spec.command_line());
+        match self.processes.get_mut(id) {
+            Some(existing) => existing.replace_child(spec, child),
+            None => {
+                let mut process = ManagedProcess::new(id.to_string(), spec, child);
+                if let Some(dir) = &self.log_dir {
+                    match ProcessLog::open(dir, id, self.log_rotation.clone()) {
+                        Ok(log) => process.set_log(log),
+                        Err(e) => eprintln!("Failed to open log file for process '{}': {}", id, e),
+                    }
+                }
+                self.processes.insert(id.to_string(), process);
+            }
+        }
+        self.record_output(id, OutputStream::System, started);
+        println!("Total managed processes: {}", self.processes.len());
+    }
+
+    // 根据基础名称生成未被占用的 id
+    pub fn unique_id(&self, base: &str) -> String {
+        if !self.processes.contains_key(base) {
+            return base.to_string();
+        }
+        (2..)
+            .map(|n| format!("{}-{}", base, n))
+            .find(|id| !self.processes.contains_key(id))
+            .unwrap()
+    }
+
+    // 进程退出后记录退出状态并清除其句柄，避免之后误杀复用的 pid
+    pub fn mark_exited(&mut self, id: &str, pid: u32, code: Option<i32>, signal: Option<i32>) {
+        if let Some(process) = self.processes.get_mut(id) {
+            if process.mark_exited(pid, code, signal) {
+                let exited = format!("Process exited (pid {}, code: {:?}, signal: {:?})", pid, code, signal);
+                process.record_output(OutputStream::System, exited);
+            }
+        }
+    }
+
+    pub fn record_output(&mut self, id: &str, stream: OutputStream, line: String) {
+        if let Some(process) = self.processes.get_mut(id) {
+            process.record_output(stream, line);
+        }
+    }
+
+    pub fn list(&self) -> Vec<ProcessInfo> {
+        let mut infos: Vec<ProcessInfo> = self.processes.values().map(|p| p.info()).collect();
+        infos.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)));
+        infos
+    }
+
+    pub fn spec(&self, id: &str) -> Option<ProcessSpec> {
+        self.processes.get(id).map(|p| p.spec.clone())
+    }
+
+    pub fn tail(&self, id: &str, lines: usize) -> Result<Vec<OutputLine>, String> {
+        self.processes
+            .get(id)
+            .map(|p| p.tail(lines))
+            .ok_or_else(|| format!("Unknown process: {}", id))
+    }
+
+    // 日志目录和轮转策略；读取日志文件较慢，调用方应在释放锁之后再读取
+    pub fn log_location(&self) -> Result<(PathBuf, LogRotation), String> {
+        self.log_dir
+            .clone()
+            .map(|dir| (dir, self.log_rotation.clone()))
+            .ok_or_else(|| "Process log directory is not configured".to_string())
+    }
+
+    // 最近的 stderr 输出，用于报告启动失败原因
+    pub fn recent_stderr(&self, id: &str, lines: usize) -> Vec<String> {
+        self.processes
+            .get(id)
+            .map(|p| p.recent_lines(OutputStream::Stderr, lines))
+            .unwrap_or_default()
+    }
+
+    // 取出进程的句柄交由调用方结束；stop_supervision 为 false 时受监督的 cline-core 会被当作崩溃并自动重启
+    pub fn take_child(&mut self, id: &str, stop_supervision: bool) -> Result<Option<CommandChild>, String> {
+        if stop_supervision && id == CLINE_CORE_ID {
+            // 主动停止，通知监督任务不要重启
+            self.supervision_epoch += 1;
+        }
+
+        let process = self
+            .processes
+            .get_mut(id)
+            .ok_or_else(|| format!("Unknown process: {}", id))?;
+        Ok(process.take_child())
+    }
+
+    // 正在运行的进程的 id 和 pid，用于资源采样
+    pub fn running_processes(&self) -> Vec<(String, u32)> {
+        self.processes
+            .values()
+            .filter(|p| p.is_running())
+            .filter_map(|p| p.pid.map(|pid| (p.id.clone(), pid)))
+            .collect()
+    }
+
+    pub fn set_stats(&mut self, stats: Vec<ProcessStats>) {
+        self.stats = stats;
+    }
+
+    pub fn stats(&self) -> Vec<ProcessStats> {
+        self.stats.clone()
+    }
+
+    pub fn supervision_epoch(&self) -> u64 {
+        self.supervision_epoch
+    }
+
+    // 监督任务仅在 epoch 未变化时（即没有被主动停止）才重启进程
+    pub fn should_restart(&self, epoch: u64) -> bool {
+        self.supervision_epoch == epoch
+    }
+
+    pub fn restart_policy(&self) -> &RestartPolicy {
+        &self.restart_policy
+    }
+
+    pub fn shutdown_config(&self) -> &ShutdownConfig {
+        &self.shutdown_config
+    }
+
+    pub fn shutdown_coordinator(&self) -> Arc<ShutdownCoordinator> {
+        self.shutdown.clone()
+    }
+
+    // 等待所有日志写入磁盘，应用退出时写入线程会随进程一起结束
+    pub fn flush_logs(&self) {
+        for process in self.processes.values() {
+            process.flush_log();
+        }
+    }
+
+    // 取出所有仍在运行的子进程，交由调用方结束
+    pub fn take_all_children(&mut self) -> Vec<(String, CommandChild)> {
+        // 主动停止，通知监督任务不要重启
+        self.supervision_epoch += 1;
+
+        self.processes
+            .values_mut()
+            .filter_map(|p| p.take_child().map(|child| (p.id.clone(), child)))
+            .collect()
+    }
+}
+
+// 创建一个全局的进程管理器
+pub type SharedProcessManager = Arc<Mutex<ProcessManager>>;
+
+pub fn create_process_manager() -> SharedProcessManager {
+    Arc::new(Mutex::new(ProcessManager::new()))
+}
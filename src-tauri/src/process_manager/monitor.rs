@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::cline_core::emit_to_main;
+use super::stats::{self, ProcessStats};
+use super::{node_server, shutdown, SharedProcessManager, CLINE_CORE_ID};
+use crate::settings::MonitoringSettings;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+// 资源监控配置
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    pub interval: Duration,
+    pub memory_limit_bytes: Option<u64>,
+    pub memory_limits_bytes: HashMap<String, u64>,
+}
+
+impl MonitorConfig {
+    pub fn from_settings(settings: &MonitoringSettings) -> Self {
+        Self {
+            interval: Duration::from_millis(settings.interval_ms),
+            memory_limit_bytes: settings.memory_limit_mb.map(|mb| mb * BYTES_PER_MB),
+            memory_limits_bytes: settings
+                .memory_limits_mb
+                .iter()
+                .map(|(id, mb)| (id.clone(), mb * BYTES_PER_MB))
+                .collect(),
+        }
+    }
+
+    pub fn memory_limit_for(&self, id: &str) -> Option<u64> {
+        self.memory_limits_bytes.get(id).copied().or(self.memory_limit_bytes)
+    }
+}
+
+// 子进程超过内存上限、即将被重启时发送给前端的事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryLimitExceeded {
+    pub id: String,
+    pub pid: u32,
+    pub rss_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+// 在后台定期采样所有受管进程的资源占用，并发送 process-stats 事件
+pub fn start(app_handle: AppHandle, process_manager: SharedProcessManager, config: MonitorConfig) {
+    if config.interval.is_zero() {
+        println!("[MONITOR] Process monitoring is disabled");
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let clock_ticks = stats::clock_ticks_per_sec();
+        // 上次采样的 (pid, CPU 节拍, 时间)，用于计算 CPU 占用
+        let mut previous: HashMap<String, (u32, u64, Instant)> = HashMap::new();
+
+        loop {
+            tokio::time::sleep(config.interval).await;
+
+            let running = match process_manager.lock() {
+                Ok(manager) => manager.running_processes(),
+                Err(_) => break,
+            };
+            if running.is_empty() {
+                previous.clear();
+                continue;
+            }
+
+            let all_stats = match tauri::async_runtime::spawn_blocking(stats::read_all_stats).await {
+                Ok(all_stats) => all_stats,
+                Err(e) => {
+                    eprintln!("[MONITOR] Failed to read process stats: {}", e);
+                    continue;
+                }
+            };
+
+            let now = Instant::now();
+            let timestamp = chrono::Utc::now().timestamp_millis();
+            let mut samples = Vec::new();
+            for (id, pid) in running {
+                let usage = match stats::tree_usage(pid, &all_stats) {
+                    Some(usage) => usage,
+                    None => continue,
+                };
+                let cpu_percent = match previous.get(&id) {
+                    Some((prev_pid, prev_ticks, prev_time)) if *prev_pid == pid => {
+                        stats::cpu_percent(*prev_ticks, usage.cpu_ticks, now - *prev_time, clock_ticks)
+                    }
+                    _ => 0.0,
+                };
+                previous.insert(id.clone(), (pid, usage.cpu_ticks, now));
+
+                samples.push(ProcessStats {
+                    id,
+                    pid,
+                    cpu_percent,
+                    rss_bytes: usage.rss_bytes,
+                    open_fds: usage.open_fds,
+                    process_count: usage.process_count,
+                    timestamp,
+                });
+            }
+            previous.retain(|id, _| samples.iter().any(|s| &s.id == id));
+
+            if let Ok(mut manager) = process_manager.lock() {
+                manager.set_stats(samples.clone());
+            }
+            emit_to_main(&app_handle, "process-stats", samples.clone());
+
+            for sample in samples {
+                if let Some(limit_bytes) = config.memory_limit_for(&sample.id) {
+                    if sample.rss_bytes > limit_bytes {
+                        previous.remove(&sample.id);
+                        // 优雅退出需要等待宽限期，不占用异步运行时的工作线程
+                        let app_handle = app_handle.clone();
+                        let process_manager = process_manager.clone();
+                        let restart = tauri::async_runtime::spawn_blocking(move || {
+                            restart_over_limit(&app_handle, &process_manager, &sample, limit_bytes)
+                        });
+                        if let Err(e) = restart.await {
+                            eprintln!("[MONITOR] Failed to restart process: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn restart_over_limit(
+    app_handle: &AppHandle,
+    process_manager: &SharedProcessManager,
+    sample: &ProcessStats,
+    limit_bytes: u64,
+) {
+    eprintln!(
+        "[MONITOR] Process '{}' (pid {}) uses {} MB, exceeding the {} MB limit, restarting",
+        sample.id,
+        sample.pid,
+        sample.rss_bytes / BYTES_PER_MB,
+        limit_bytes / BYTES_PER_MB
+    );
+    emit_to_main(app_handle, "process-memory-limit-exceeded", MemoryLimitExceeded {
+        id: sample.id.clone(),
+        pid: sample.pid,
+        rss_bytes: sample.rss_bytes,
+        limit_bytes,
+    });
+
+    // 先发送 SIGTERM 让进程保存任务状态，超过宽限期后才强制结束
+    let result = if sample.id == CLINE_CORE_ID {
+        // 不改变监督周期，监督任务会把它当作崩溃并按退避策略重启
+        shutdown::stop_gracefully(process_manager, &sample.id, false)
+    } else {
+        let spec = process_manager.lock().unwrap().spec(&sample.id);
+        match spec {
+            Some(spec) => shutdown::stop_gracefully(process_manager, &sample.id, true)
+                .and_then(|_| node_server::start(app_handle, process_manager, &sample.id, spec))
+                .map(|_| ()),
+            None => Err(format!("Unknown process: {}", sample.id)),
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("[MONITOR] Failed to restart process '{}': {}", sample.id, e);
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::CommandEvent;
+
+use super::{OutputStream, ProcessKind, ProcessSpec, SharedProcessManager};
+
+// sidecar 输出该标记后视为服务就绪
+const NODE_SERVER_READY_FLAG: &str = "NODE_SERVER_READY";
+
+// 以 node 运行脚本的启动配置
+pub fn node_script_spec(script_path: String, args: Vec<String>) -> ProcessSpec {
+    let name = std::path::Path::new(&script_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "node".to_string());
+
+    let mut all_args = vec![script_path];
+    all_args.extend(args);
+
+    ProcessSpec {
+        name,
+        kind: ProcessKind::Command,
+        program: "node".to_string(),
+        args: all_args,
+        cwd: None,
+        port: None,
+        env: HashMap::new(),
+    }
+}
+
+// node-server sidecar 的启动配置
+pub fn sidecar_spec(port: u16) -> ProcessSpec {
+    ProcessSpec {
+        name: "node-server".to_string(),
+        kind: ProcessKind::Sidecar,
+        program: "node-server".to_string(),
+        args: vec![port.to_string()],
+        cwd: None,
+        port: Some(port),
+        env: HashMap::new(),
+    }
+}
+
+// 启动进程并以 id 登记到进程管理器中，同一 id 已存在时替换
+pub fn start(
+    app_handle: &AppHandle,
+    process_manager: &SharedProcessManager,
+    id: &str,
+    spec: ProcessSpec,
+) -> Result<u32, String> {
+    let (rx, child) = spec.spawn(app_handle)?;
+    let pid = child.pid();
+    let ready_port = match spec.kind {
+        ProcessKind::Sidecar => spec.port,
+        ProcessKind::Command => None,
+    };
+
+    // 将子进程添加到进程管理器中
+    {
+        let mut manager = process_manager.lock().unwrap();
+        manager.register(id, spec, child);
+    }
+
+    tauri::async_runtime::spawn(forward_node_output(
+        app_handle.clone(),
+        process_manager.clone(),
+        id.to_string(),
+        pid,
+        rx,
+        ready_port,
+    ));
+
+    Ok(pid)
+}
+
+// 处理Node.js进程的输出，记录到进程管理器并转发给前端
+async fn forward_node_output(
+    handle: AppHandle,
+    process_manager: SharedProcessManager,
+    id: String,
+    pid: u32,
+    mut rx: Receiver<CommandEvent>,
+    ready_port: Option<u16>,
+) {
+    let mut server_ready = false;
+
+    while let Some(event) = rx.recv().await {
+        let window = handle.get_webview_window("main");
+        match event {
+            CommandEvent::Stdout(line) => {
+                let line_str = String::from_utf8_lossy(&line).to_string();
+                println!("Node.js [{}] stdout: {}", id, line_str);
+                record_output(&process_manager, &id, OutputStream::Stdout, &line_str);
+
+                if let Some(window) = window {
+                    // 检查 sidecar 服务器是否准备就绪
+                    if let Some(port) = ready_port {
+                        if !server_ready && line_str.contains(NODE_SERVER_READY_FLAG) {
+                            server_ready = true;
+                            window
+                                .emit("node-server-ready", port)
+                                .expect("failed to emit ready event");
+                        }
+                    }
+
+                    window
+                        .emit("node-stdout", line_str)
+                        .expect("failed to emit event");
+                }
+            }
+            CommandEvent::Stderr(line) => {
+                let line_str = String::from_utf8_lossy(&line).to_string();
+                eprintln!("Node.js [{}] stderr: {}", id, line_str);
+                record_output(&process_manager, &id, OutputStream::Stderr, &line_str);
+
+                if let Some(window) = window {
+                    window
+                        .emit("node-stderr", line_str)
+                        .expect("failed to emit event");
+                }
+            }
+            CommandEvent::Error(err) => {
+                eprintln!("Node.js [{}] error: {}", id, err);
+                if let Some(window) = window {
+                    window
+                        .emit("node-error", err.to_string())
+                        .expect("failed to emit event");
+                }
+            }
+            CommandEvent::Terminated(status) => {
+                println!("Node.js [{}] terminated with status: {:?}", id, status);
+                if let Ok(mut manager) = process_manager.lock() {
+                    manager.mark_exited(&id, pid, status.code, status.signal);
+                }
+                if let Some(window) = window {
+                    window
+                        .emit("node-terminated", status)
+                        .expect("failed to emit event");
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn record_output(process_manager: &SharedProcessManager, id: &str, stream: OutputStream, line: &str) {
+    if let Ok(mut manager) = process_manager.lock() {
+        manager.record_output(id, stream, line.trim_end().to_string());
+    }
+}
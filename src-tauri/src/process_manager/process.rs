@@ -0,0 +1,279 @@
+use std::collections::{HashMap, VecDeque};
+use serde::Serialize;
+use tauri::async_runtime::Receiver;
+use tauri::AppHandle;
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+
+use super::log_file::ProcessLog;
+
+// 每个进程在内存中保留的最近输出行数
+pub const MAX_OUTPUT_LINES: usize = 1000;
+
+// 子进程的启动方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessKind {
+    // 通过 shell 插件直接执行的程序（如 node）
+    Command,
+    // tauri.conf.json 中声明的 sidecar
+    Sidecar,
+}
+
+// 启动子进程所需的全部信息，用于重启
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessSpec {
+    pub name: String,
+    pub kind: ProcessKind,
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub port: Option<u16>,
+    pub env: HashMap<String, String>,
+}
+
+impl ProcessSpec {
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(|a| a.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // 按照配置启动进程
+    pub fn spawn(&self, app_handle: &AppHandle) -> Result<(Receiver<CommandEvent>, CommandChild), String> {
+        let command = match self.kind {
+            // 在新的会话中启动，使子进程及其派生的进程组成独立的进程组，关闭时可以整组结束
+            ProcessKind::Command => match session_wrapper() {
+                Some(setsid) => app_handle.shell().command(setsid).arg(&self.program),
+                None => app_handle.shell().command(&self.program),
+            },
+            ProcessKind::Sidecar => app_handle
+                .shell()
+                .sidecar(&self.program)
+                .map_err(|e| format!("Failed to get sidecar command: {}", e))?,
+        };
+
+        let mut command = command.args(&self.args);
+        if let Some(cwd) = &self.cwd {
+            command = command.current_dir(cwd);
+        }
+        if !self.env.is_empty() {
+            command = command.envs(self.env.clone());
+        }
+
+        command
+            .spawn()
+            .map_err(|e| format!("Failed to spawn {}: {}", self.name, e))
+    }
+}
+
+// setsid 不会额外 fork（子进程不是进程组组长），因此得到的 pid 仍是目标程序本身
+#[cfg(target_os = "linux")]
+fn session_wrapper() -> Option<&'static str> {
+    ["/usr/bin/setsid", "/bin/setsid"]
+        .into_iter()
+        .find(|path| std::path::Path::new(path).exists())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn session_wrapper() -> Option<&'static str> {
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+    // 进程管理器记录的启动、退出等事件
+    System,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputLine {
+    pub timestamp: i64,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+// 固定容量的输出缓冲区，超出时丢弃最旧的行
+#[derive(Debug)]
+pub struct OutputBuffer {
+    lines: VecDeque<OutputLine>,
+    capacity: usize,
+}
+
+impl OutputBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, stream: OutputStream, line: String) -> &OutputLine {
+        if self.lines.len() >= self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(OutputLine {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            stream,
+            line,
+        });
+        self.lines.back().unwrap()
+    }
+
+    // 返回最后 n 行，按时间顺序排列
+    pub fn tail(&self, n: usize) -> Vec<OutputLine> {
+        let skip = self.lines.len().saturating_sub(n);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+
+    // 返回指定输出流的最后 n 行文本
+    pub fn recent_lines(&self, stream: OutputStream, n: usize) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .lines
+            .iter()
+            .rev()
+            .filter(|l| l.stream == stream)
+            .take(n)
+            .map(|l| l.line.clone())
+            .collect();
+        lines.reverse();
+        lines
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitStatusInfo {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub exited_at: i64,
+}
+
+// 提供给前端进程面板的进程信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessInfo {
+    pub id: String,
+    pub name: String,
+    pub kind: ProcessKind,
+    pub pid: Option<u32>,
+    pub command_line: String,
+    pub started_at: i64,
+    pub port: Option<u16>,
+    pub running: bool,
+    pub restarts: u32,
+    pub last_exit: Option<ExitStatusInfo>,
+}
+
+// 进程管理器中登记的单个子进程
+pub struct ManagedProcess {
+    pub id: String,
+    pub spec: ProcessSpec,
+    pub pid: Option<u32>,
+    child: Option<CommandChild>,
+    started_at: i64,
+    restarts: u32,
+    last_exit: Option<ExitStatusInfo>,
+    output: OutputBuffer,
+    // 持久化的日志文件，未配置日志目录时为 None
+    log: Option<ProcessLog>,
+}
+
+impl ManagedProcess {
+    pub fn new(id: String, spec: ProcessSpec, child: CommandChild) -> Self {
+        Self {
+            id,
+            spec,
+            pid: Some(child.pid()),
+            child: Some(child),
+            started_at: chrono::Utc::now().timestamp_millis(),
+            restarts: 0,
+            last_exit: None,
+            output: OutputBuffer::new(MAX_OUTPUT_LINES),
+            log: None,
+        }
+    }
+
+    pub fn set_log(&mut self, log: ProcessLog) {
+        self.log = Some(log);
+    }
+
+    // 用新启动的进程替换旧进程，保留输出历史
+    pub fn replace_child(&mut self, spec: ProcessSpec, child: CommandChild) {
+        self.spec = spec;
+        self.pid = Some(child.pid());
+        self.child = Some(child);
+        self.started_at = chrono::Utc::now().timestamp_millis();
+        self.restarts += 1;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.child.is_some()
+    }
+
+    pub fn take_child(&mut self) -> Option<CommandChild> {
+        self.child.take()
+    }
+
+    // 仅当 pid 匹配当前进程时记录退出状态
+    pub fn mark_exited(&mut self, pid: u32, code: Option<i32>, signal: Option<i32>) -> bool {
+        if self.pid != Some(pid) {
+            return false;
+        }
+        self.child = None;
+        self.last_exit = Some(ExitStatusInfo {
+            code,
+            signal,
+            exited_at: chrono::Utc::now().timestamp_millis(),
+        });
+        true
+    }
+
+    pub fn record_output(&mut self, stream: OutputStream, line: String) {
+        let entry = self.output.push(stream, line);
+        if let Some(log) = &mut self.log {
+            if let Err(e) = log.append(entry) {
+                // 写入失败时停止记录，避免每行输出都报错
+                eprintln!("Failed to write log for process '{}': {}, disabling log file", self.id, e);
+                self.log = None;
+            }
+        }
+    }
+
+    pub fn flush_log(&self) {
+        if let Some(log) = &self.log {
+            if let Err(e) = log.flush() {
+                eprintln!("Failed to flush log for process '{}': {}", self.id, e);
+            }
+        }
+    }
+
+    pub fn tail(&self, n: usize) -> Vec<OutputLine> {
+        self.output.tail(n)
+    }
+
+    pub fn recent_lines(&self, stream: OutputStream, n: usize) -> Vec<String> {
+        self.output.recent_lines(stream, n)
+    }
+
+    pub fn info(&self) -> ProcessInfo {
+        ProcessInfo {
+            id: self.id.clone(),
+            name: self.spec.name.clone(),
+            kind: self.spec.kind,
+            pid: self.pid,
+            command_line: self.spec.command_line(),
+            started_at: self.started_at,
+            port: self.spec.port,
+            running: self.is_running(),
+            restarts: self.restarts,
+            last_exit: self.last_exit.clone(),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tauri_plugin_shell::process::CommandChild;
+
+use super::tree::{ProcessTree, TreeSignal};
+use super::SharedProcessManager;
+
+// 可以被强制结束的子进程句柄，测试中可以用 std::process::Child 代替
+pub trait ChildHandle {
+    fn pid(&self) -> u32;
+    fn kill(self) -> Result<(), String>;
+}
+
+impl ChildHandle for CommandChild {
+    fn pid(&self) -> u32 {
+        CommandChild::pid(self)
+    }
+
+    fn kill(self) -> Result<(), String> {
+        CommandChild::kill(self).map_err(|e| e.to_string())
+    }
+}
+
+// 默认的优雅退出等待时间，可通过环境变量覆盖
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+pub const GRACE_PERIOD_ENV: &str = "CLINE_DESKTOP_SHUTDOWN_GRACE_MS";
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// 强制结束后等待进程消失的时间
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(1);
+
+// 关闭子进程的配置
+#[derive(Debug, Clone)]
+pub struct ShutdownConfig {
+    // 发送 SIGTERM 后等待子进程自行退出的时间，超时后强制结束
+    pub grace_period: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        let grace_period = std::env::var(GRACE_PERIOD_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_GRACE_PERIOD);
+
+        Self { grace_period }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShutdownPhase {
+    Idle,
+    Running,
+    Done,
+}
+
+// 保证关闭流程只执行一次
+// Ctrl+C、CloseRequested 和 Destroyed 都会触发关闭，后到的调用会等待第一次调用完成
+#[derive(Debug)]
+pub struct ShutdownCoordinator {
+    phase: Mutex<ShutdownPhase>,
+    finished: Condvar,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self {
+            phase: Mutex::new(ShutdownPhase::Idle),
+            finished: Condvar::new(),
+        }
+    }
+
+    // 仅第一次调用会执行 f 并返回 true；其它调用阻塞到 f 执行完毕后返回 false
+    pub fn run_once<F: FnOnce()>(&self, f: F) -> bool {
+        let mut phase = self.phase.lock().unwrap_or_else(|e| e.into_inner());
+        match *phase {
+            ShutdownPhase::Idle => {
+                *phase = ShutdownPhase::Running;
+                drop(phase);
+
+                f();
+
+                *self.phase.lock().unwrap_or_else(|e| e.into_inner()) = ShutdownPhase::Done;
+                self.finished.notify_all();
+                true
+            }
+            ShutdownPhase::Running => {
+                while *phase == ShutdownPhase::Running {
+                    phase = self.finished.wait(phase).unwrap_or_else(|e| e.into_inner());
+                }
+                false
+            }
+            ShutdownPhase::Done => false,
+        }
+    }
+}
+
+// 应用退出时优雅地结束所有子进程，无论被触发多少次都只执行一次
+pub fn shutdown_gracefully(process_manager: &SharedProcessManager) {
+    let (coordinator, config) = match process_manager.lock() {
+        Ok(manager) => (manager.shutdown_coordinator(), manager.shutdown_config().clone()),
+        Err(e) => {
+            eprintln!("Process manager lock poisoned during shutdown: {}", e);
+            return;
+        }
+    };
+
+    let ran = coordinator.run_once(|| {
+        println!("Shutting down child processes (grace period: {}ms)...", config.grace_period.as_millis());
+        let children = match process_manager.lock() {
+            Ok(mut manager) => manager.take_all_children(),
+            Err(_) => Vec::new(),
+        };
+        let survivors = terminate_gracefully(children, config.grace_period);
+        if !survivors.is_empty() {
+            eprintln!("{} child processes could not be terminated: {:?}", survivors.len(), survivors);
+        }
+        if let Ok(manager) = process_manager.lock() {
+            manager.flush_logs();
+        }
+    });
+
+    if !ran {
+        println!("Child process shutdown already performed");
+    }
+}
+
+// 优雅地结束单个进程并等待其进程树退出，用于切换工作区、超过内存上限等需要保留任务状态的重启
+// 会阻塞到进程退出或被强制结束为止，调用方不能持有进程管理器的锁
+pub fn stop_gracefully(process_manager: &SharedProcessManager, id: &str, stop_supervision: bool) -> Result<(), String> {
+    let (child, grace_period) = {
+        let mut manager = process_manager.lock().map_err(|e| e.to_string())?;
+        (manager.take_child(id, stop_supervision)?, manager.shutdown_config().grace_period)
+    };
+    let child = match child {
+        Some(child) => child,
+        None => {
+            println!("Process '{}' is not running", id);
+            return Ok(());
+        }
+    };
+
+    let survivors = terminate_gracefully(vec![(id.to_string(), child)], grace_period);
+    if survivors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Processes of '{}' could not be terminated: {:?}", id, survivors))
+    }
+}
+
+// 先向整个进程树发送 SIGTERM 让子进程保存状态，超过宽限期仍未退出的进程树再强制结束
+// 返回强制结束之后仍然存活的进程 pid
+pub fn terminate_gracefully<C: ChildHandle>(children: Vec<(String, C)>, grace_period: Duration) -> Vec<u32> {
+    if children.is_empty() {
+        return Vec::new();
+    }
+
+    // 在发送信号之前记录每个子进程的进程树
+    let trees: Vec<(String, ProcessTree)> = children
+        .iter()
+        .map(|(id, child)| (id.clone(), ProcessTree::snapshot(child.pid())))
+        .collect();
+
+    println!("Sending SIGTERM to {} child process trees...", trees.len());
+    for (id, tree) in &trees {
+        println!("Terminating '{}' and its descendants ({} processes)", id, tree.len());
+        tree.signal(TreeSignal::Terminate);
+    }
+
+    // 非 Unix 平台既不能请求退出也无法检查进程树，所有子进程都直接强制结束
+    let deadline = Instant::now() + grace_period;
+    let mut pending: Vec<usize> = (0..trees.len()).collect();
+    if cfg!(unix) {
+        loop {
+            pending.retain(|&index| {
+                let (id, tree) = &trees[index];
+                let alive = !tree.survivors().is_empty();
+                if !alive {
+                    println!("Child process '{}' exited gracefully", id);
+                }
+                alive
+            });
+
+            if pending.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+            std::thread::sleep(EXIT_POLL_INTERVAL);
+        }
+    }
+
+    for (index, (id, child)) in children.into_iter().enumerate() {
+        if !pending.contains(&index) {
+            continue;
+        }
+        println!("Child process '{}' did not exit within grace period, killing its process tree", id);
+        trees[index].1.signal(TreeSignal::Kill);
+        match child.kill() {
+            Ok(_) => println!("Successfully killed child process '{}'", id),
+            Err(e) => eprintln!("Failed to kill process '{}': {}", id, e),
+        }
+    }
+
+    let survivors = verify_exited(&trees);
+    println!("Finished shutting down child processes");
+    survivors
+}
+
+// 确认所有进程树都已结束，报告仍然存活的进程
+fn verify_exited(trees: &[(String, ProcessTree)]) -> Vec<u32> {
+    let deadline = Instant::now() + VERIFY_TIMEOUT;
+    loop {
+        let survivors: Vec<(&str, Vec<u32>)> = trees
+            .iter()
+            .map(|(id, tree)| (id.as_str(), tree.survivors()))
+            .filter(|(_, pids)| !pids.is_empty())
+            .collect();
+
+        if survivors.is_empty() {
+            return Vec::new();
+        }
+        if Instant::now() >= deadline {
+            for (id, pids) in &survivors {
+                eprintln!("Processes of '{}' survived shutdown: {:?}", id, pids);
+            }
+            return survivors.into_iter().flat_map(|(_, pids)| pids).collect();
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::Serialize;
+
+// 单个受管进程（包含其所有子孙进程）的资源占用
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessStats {
+    pub id: String,
+    pub pid: u32,
+    // 自上次采样以来的 CPU 占用，100 表示占满一个核心；首次采样时为 0
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub open_fds: u64,
+    // 进程树中的进程数量
+    pub process_count: usize,
+    pub timestamp: i64,
+}
+
+// /proc/<pid>/stat 中需要的字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub ppid: u32,
+    // 进程状态，'Z' 表示僵尸进程
+    pub state: char,
+    // 进程启动时间（系统启动后的时钟节拍），与 pid 一起唯一标识一个进程
+    pub start_time: u64,
+    // utime + stime，单位为时钟节拍
+    pub cpu_ticks: u64,
+    pub rss_pages: u64,
+}
+
+// 解析 /proc/<pid>/stat；进程名可能包含空格和括号，因此从最后一个 ')' 之后开始解析
+pub fn parse_stat(content: &str) -> Option<ProcStat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let pid = content[..open].trim().parse().ok()?;
+    // 字段从 state（第 3 个字段）开始
+    let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+    let field = |n: usize| fields.get(n - 3).and_then(|f| f.parse::<u64>().ok());
+
+    Some(ProcStat {
+        pid,
+        ppid: field(4)? as u32,
+        state: fields.first()?.chars().next()?,
+        start_time: field(22)?,
+        cpu_ticks: field(14)? + field(15)?,
+        rss_pages: field(24)?,
+    })
+}
+
+// 按父子关系找出 root 及其所有子孙进程
+pub fn process_tree(root: u32, processes: &[ProcStat]) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for process in processes {
+        children.entry(process.ppid).or_default().push(process.pid);
+    }
+
+    let mut tree = vec![root];
+    let mut index = 0;
+    while index < tree.len() {
+        if let Some(pids) = children.get(&tree[index]) {
+            tree.extend(pids.iter().copied().filter(|pid| *pid != root));
+        }
+        index += 1;
+    }
+    tree
+}
+
+// 两次采样之间的 CPU 占用百分比
+pub fn cpu_percent(previous_ticks: u64, ticks: u64, elapsed: Duration, clock_ticks_per_sec: u64) -> f64 {
+    let elapsed_secs = elapsed.as_secs_f64();
+    if elapsed_secs <= 0.0 || clock_ticks_per_sec == 0 {
+        return 0.0;
+    }
+    let cpu_secs = ticks.saturating_sub(previous_ticks) as f64 / clock_ticks_per_sec as f64;
+    cpu_secs / elapsed_secs * 100.0
+}
+
+// 一次采样得到的进程树汇总，CPU 百分比由调用方根据上次采样计算
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeUsage {
+    pub cpu_ticks: u64,
+    pub rss_bytes: u64,
+    pub open_fds: u64,
+    pub process_count: usize,
+}
+
+// 读取单个进程的 stat，进程不存在时返回 None
+#[cfg(target_os = "linux")]
+pub fn read_stat(pid: u32) -> Option<ProcStat> {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|content| parse_stat(&content))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_stat(_pid: u32) -> Option<ProcStat> {
+    None
+}
+
+// 读取当前系统中所有进程的 stat，用于构建进程树
+#[cfg(target_os = "linux")]
+pub fn read_all_stats() -> Vec<ProcStat> {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+        .filter_map(|content| parse_stat(&content))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_all_stats() -> Vec<ProcStat> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn count_open_fds(pid: u32) -> u64 {
+    std::fs::read_dir(format!("/proc/{}/fd", pid))
+        .map(|entries| entries.count() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count_open_fds(_pid: u32) -> u64 {
+    0
+}
+
+// 汇总 root 进程树的资源占用；root 已不存在时返回 None
+pub fn tree_usage(root: u32, processes: &[ProcStat]) -> Option<TreeUsage> {
+    if !processes.iter().any(|p| p.pid == root) {
+        return None;
+    }
+
+    let page_size = page_size();
+    let by_pid: HashMap<u32, &ProcStat> = processes.iter().map(|p| (p.pid, p)).collect();
+    let tree = process_tree(root, processes);
+
+    let mut usage = TreeUsage {
+        cpu_ticks: 0,
+        rss_bytes: 0,
+        open_fds: 0,
+        process_count: 0,
+    };
+    for pid in tree {
+        if let Some(stat) = by_pid.get(&pid) {
+            usage.cpu_ticks += stat.cpu_ticks;
+            usage.rss_bytes += stat.rss_pages * page_size;
+            usage.open_fds += count_open_fds(pid);
+            usage.process_count += 1;
+        }
+    }
+    Some(usage)
+}
+
+#[cfg(unix)]
+pub fn clock_ticks_per_sec() -> u64 {
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 { ticks as u64 } else { 100 }
+}
+
+#[cfg(not(unix))]
+pub fn clock_ticks_per_sec() -> u64 {
+    100
+}
+
+#[cfg(unix)]
+fn page_size() -> u64 {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as u64 } else { 4096 }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> u64 {
+    4096
+}
@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::grpc_client::utils::RetryConfig;
+
+// cline-core 崩溃重启策略
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    // 退避参数；max_retries 表示在 restart_window 内允许的最大重启次数
+    pub retry_config: RetryConfig,
+    // 统计重启次数的滑动时间窗口
+    pub restart_window: Duration,
+    // 进程连续运行超过该时长即视为稳定，退避延迟重新从 initial_delay 开始
+    pub stable_uptime: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            retry_config: RetryConfig::with_delays(
+                5,
+                Duration::from_millis(500),
+                Duration::from_secs(30),
+            ),
+            restart_window: Duration::from_secs(300), // 5分钟内最多重启5次
+            stable_uptime: Duration::from_secs(60),
+        }
+    }
+}
+
+// 子进程退出后的处理决定
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestartDecision {
+    Restart { attempt: usize, delay: Duration },
+    GiveUp { restarts: usize },
+}
+
+// 记录重启历史，根据策略决定是否以及何时重启
+#[derive(Debug)]
+pub struct RestartTracker {
+    policy: RestartPolicy,
+    restarts: VecDeque<Instant>,
+    consecutive_failures: usize,
+}
+
+impl RestartTracker {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            restarts: VecDeque::new(),
+            consecutive_failures: 0,
+        }
+    }
+
+    pub fn policy(&self) -> &RestartPolicy {
+        &self.policy
+    }
+
+    // 子进程意外退出时调用，uptime 为本次运行时长
+    pub fn on_exit(&mut self, now: Instant, uptime: Duration) -> RestartDecision {
+        // 丢弃窗口之外的重启记录
+        while let Some(&restarted_at) = self.restarts.front() {
+            if now.duration_since(restarted_at) > self.policy.restart_window {
+                self.restarts.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        // 运行足够久之后再崩溃，不再沿用之前的退避
+        if uptime >= self.policy.stable_uptime {
+            self.consecutive_failures = 0;
+        }
+
+        if self.restarts.len() >= self.policy.retry_config.max_retries {
+            return RestartDecision::GiveUp {
+                restarts: self.restarts.len(),
+            };
+        }
+
+        self.consecutive_failures += 1;
+        self.restarts.push_back(now);
+
+        RestartDecision::Restart {
+            attempt: self.restarts.len(),
+            delay: self.policy.retry_config.delay_for_attempt(self.consecutive_failures),
+        }
+    }
+}
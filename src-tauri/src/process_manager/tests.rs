@@ -0,0 +1,602 @@
+#[cfg(test)]
+mod supervisor_tests {
+    use crate::grpc_client::utils::RetryConfig;
+    use crate::process_manager::{RestartDecision, RestartPolicy, RestartTracker};
+    use std::time::{Duration, Instant};
+
+    fn create_test_policy() -> RestartPolicy {
+        RestartPolicy {
+            retry_config: RetryConfig::with_delays(
+                3,
+                Duration::from_millis(100),
+                Duration::from_millis(1000),
+            ),
+            restart_window: Duration::from_secs(60),
+            stable_uptime: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn test_restart_with_exponential_backoff() {
+        let mut tracker = RestartTracker::new(create_test_policy());
+        let now = Instant::now();
+
+        assert_eq!(
+            tracker.on_exit(now, Duration::from_secs(1)),
+            RestartDecision::Restart { attempt: 1, delay: Duration::from_millis(100) }
+        );
+        assert_eq!(
+            tracker.on_exit(now + Duration::from_secs(1), Duration::from_secs(1)),
+            RestartDecision::Restart { attempt: 2, delay: Duration::from_millis(200) }
+        );
+        assert_eq!(
+            tracker.on_exit(now + Duration::from_secs(2), Duration::from_secs(1)),
+            RestartDecision::Restart { attempt: 3, delay: Duration::from_millis(400) }
+        );
+    }
+
+    #[test]
+    fn test_gives_up_after_max_restarts_in_window() {
+        let mut tracker = RestartTracker::new(create_test_policy());
+        let now = Instant::now();
+
+        for i in 0..3 {
+            assert!(matches!(
+                tracker.on_exit(now + Duration::from_secs(i), Duration::ZERO),
+                RestartDecision::Restart { .. }
+            ));
+        }
+
+        assert_eq!(
+            tracker.on_exit(now + Duration::from_secs(5), Duration::ZERO),
+            RestartDecision::GiveUp { restarts: 3 }
+        );
+    }
+
+    #[test]
+    fn test_old_restarts_leave_window() {
+        let mut tracker = RestartTracker::new(create_test_policy());
+        let now = Instant::now();
+
+        for i in 0..3 {
+            tracker.on_exit(now + Duration::from_secs(i), Duration::ZERO);
+        }
+
+        // 窗口之外的重启不再计数
+        let later = now + Duration::from_secs(120);
+        assert!(matches!(
+            tracker.on_exit(later, Duration::ZERO),
+            RestartDecision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_stable_uptime_resets_backoff() {
+        let mut tracker = RestartTracker::new(create_test_policy());
+        let now = Instant::now();
+
+        tracker.on_exit(now, Duration::ZERO);
+        tracker.on_exit(now + Duration::from_secs(1), Duration::ZERO);
+
+        // 稳定运行后再崩溃，延迟回到初始值，但仍计入窗口内的重启次数
+        assert_eq!(
+            tracker.on_exit(now + Duration::from_secs(40), Duration::from_secs(35)),
+            RestartDecision::Restart { attempt: 3, delay: Duration::from_millis(100) }
+        );
+    }
+}
+
+#[cfg(test)]
+mod process_tests {
+    use crate::process_manager::process::{OutputBuffer, OutputStream, ProcessKind, ProcessSpec};
+    use crate::process_manager::node_server::{node_script_spec, sidecar_spec};
+
+    #[test]
+    fn test_output_buffer_drops_oldest_lines() {
+        let mut buffer = OutputBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(OutputStream::Stdout, format!("line {}", i));
+        }
+
+        assert_eq!(buffer.len(), 3);
+        let lines: Vec<String> = buffer.tail(10).into_iter().map(|l| l.line).collect();
+        assert_eq!(lines, vec!["line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn test_output_buffer_tail() {
+        let mut buffer = OutputBuffer::new(10);
+        buffer.push(OutputStream::Stdout, "out".to_string());
+        buffer.push(OutputStream::Stderr, "err".to_string());
+
+        let tail = buffer.tail(1);
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].line, "err");
+        assert_eq!(tail[0].stream, OutputStream::Stderr);
+        assert!(buffer.tail(0).is_empty());
+    }
+
+    #[test]
+    fn test_process_spec_command_line() {
+        let spec = ProcessSpec {
+            name: "cline-core".to_string(),
+            kind: ProcessKind::Command,
+            program: "node".to_string(),
+            args: vec!["cline-core.js".to_string(), "--verbose".to_string()],
+            cwd: None,
+            port: None,
+            env: Default::default(),
+        };
+        assert_eq!(spec.command_line(), "node cline-core.js --verbose");
+    }
+
+    #[test]
+    fn test_node_specs() {
+        let spec = node_script_spec("servers/mcp-helper.js".to_string(), vec!["--port".to_string(), "3000".to_string()]);
+        assert_eq!(spec.name, "mcp-helper");
+        assert_eq!(spec.args, vec!["servers/mcp-helper.js", "--port", "3000"]);
+
+        let sidecar = sidecar_spec(8080);
+        assert_eq!(sidecar.kind, ProcessKind::Sidecar);
+        assert_eq!(sidecar.port, Some(8080));
+        assert_eq!(sidecar.args, vec!["8080"]);
+    }
+}
+
+#[cfg(test)]
+mod shutdown_tests {
+    use crate::process_manager::shutdown::{terminate_gracefully, ChildHandle, ShutdownCoordinator};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn test_shutdown_runs_exactly_once() {
+        let coordinator = Arc::new(ShutdownCoordinator::new());
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        // 模拟 Ctrl+C、CloseRequested 和 Destroyed 同时触发关闭
+        let handles: Vec<_> = (0..3).map(|_| {
+            let coordinator = coordinator.clone();
+            let counter = counter.clone();
+            std::thread::spawn(move || {
+                coordinator.run_once(|| {
+                    std::thread::sleep(Duration::from_millis(50));
+                    counter.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+        }).collect();
+
+        let ran: Vec<bool> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert_eq!(ran.iter().filter(|r| **r).count(), 1);
+
+        // 关闭完成之后的调用不会再次执行
+        assert!(!coordinator.run_once(|| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_waiting_callers_return_after_shutdown_finishes() {
+        let coordinator = Arc::new(ShutdownCoordinator::new());
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let first = {
+            let coordinator = coordinator.clone();
+            let finished = finished.clone();
+            std::thread::spawn(move || {
+                coordinator.run_once(|| {
+                    std::thread::sleep(Duration::from_millis(100));
+                    finished.store(1, Ordering::SeqCst);
+                })
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(20));
+        // 第二次调用必须等待第一次关闭流程结束
+        assert!(!coordinator.run_once(|| {}));
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        assert!(first.join().unwrap());
+    }
+
+    // 共享的 std 子进程，结束之后测试仍然可以检查它的退出状态
+    struct TestChild(Arc<Mutex<std::process::Child>>);
+
+    impl ChildHandle for TestChild {
+        fn pid(&self) -> u32 {
+            self.0.lock().unwrap().id()
+        }
+
+        fn kill(self) -> Result<(), String> {
+            self.0.lock().unwrap().kill().map_err(|e| e.to_string())
+        }
+    }
+
+    #[cfg(any(target_os = "linux", not(unix)))]
+    fn spawn_long_running() -> Arc<Mutex<std::process::Child>> {
+        let child = if cfg!(unix) {
+            std::process::Command::new("sleep").arg("60").spawn()
+        } else {
+            std::process::Command::new("cmd").args(["/C", "ping -n 60 127.0.0.1 > NUL"]).spawn()
+        };
+        Arc::new(Mutex::new(child.unwrap()))
+    }
+
+    #[cfg(any(target_os = "linux", not(unix)))]
+    fn wait_for_exit(child: &Mutex<std::process::Child>) -> bool {
+        for _ in 0..50 {
+            if child.lock().unwrap().try_wait().unwrap().is_some() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_terminate_gracefully_without_children() {
+        assert!(terminate_gracefully(Vec::<(String, TestChild)>::new(), Duration::from_secs(5)).is_empty());
+    }
+
+    // 子进程收到 SIGTERM 后成为僵尸进程，依赖 /proc 才能判断它已经退出
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_terminate_gracefully_ends_child() {
+        let child = spawn_long_running();
+
+        let survivors = terminate_gracefully(
+            vec![("cline-core".to_string(), TestChild(child.clone()))],
+            Duration::from_secs(2),
+        );
+
+        assert!(survivors.is_empty(), "processes survived: {:?}", survivors);
+        assert!(wait_for_exit(&child), "child process is still running");
+    }
+
+    // 非 Unix 平台无法检查进程树，子进程必须被直接结束而不是当作已经退出
+    #[cfg(not(unix))]
+    #[test]
+    fn test_terminate_gracefully_kills_child_without_process_tree() {
+        let child = spawn_long_running();
+
+        terminate_gracefully(
+            vec![("cline-core".to_string(), TestChild(child.clone()))],
+            Duration::from_millis(100),
+        );
+
+        assert!(wait_for_exit(&child), "child process was left running");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_tree_kill_reaches_grandchildren() {
+        use crate::process_manager::tree::{ProcessTree, TreeSignal};
+        use std::os::unix::process::CommandExt;
+
+        // sh 在自己的进程组中启动一个后台 sleep，模拟 cline-core 启动的 MCP 服务器
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 60 & wait"])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+
+        let mut tree = ProcessTree::snapshot(child.id());
+        for _ in 0..50 {
+            if tree.len() >= 2 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            tree = ProcessTree::snapshot(child.id());
+        }
+        assert!(tree.len() >= 2, "grandchild was not found in the process tree");
+        assert_eq!(tree.pgid, Some(child.id()));
+
+        tree.signal(TreeSignal::Kill);
+        let _ = child.wait();
+
+        let mut survivors = tree.survivors();
+        for _ in 0..50 {
+            if survivors.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            survivors = tree.survivors();
+        }
+        assert!(survivors.is_empty(), "processes survived: {:?}", survivors);
+    }
+}
+
+#[cfg(test)]
+mod cline_core_config_tests {
+    use crate::process_manager::cline_core::{build_cline_core_spec, resolve_cline_core_dir, CLINE_CORE_ENTRY};
+    use crate::ports::{is_address_in_use, ServicePorts};
+    use crate::settings::{ClineCoreSettings, DesktopSettings};
+    use std::path::PathBuf;
+
+    // 创建包含 cline-core.js 的临时目录
+    fn create_dist_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cline-desktop-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(CLINE_CORE_ENTRY), "// test").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_resolve_prefers_explicit_dir() {
+        let explicit = create_dist_dir("explicit");
+        let fallback = create_dist_dir("fallback");
+
+        let resolved = resolve_cline_core_dir(Some(&explicit), &[fallback.clone()]).unwrap();
+        assert_eq!(resolved, explicit);
+
+        let _ = std::fs::remove_dir_all(explicit);
+        let _ = std::fs::remove_dir_all(fallback);
+    }
+
+    #[test]
+    fn test_resolve_rejects_invalid_explicit_dir() {
+        let fallback = create_dist_dir("fallback-invalid");
+        let missing = std::env::temp_dir().join("cline-desktop-test-missing-dir");
+
+        let result = resolve_cline_core_dir(Some(&missing), &[fallback.clone()]);
+        assert!(result.is_err());
+
+        let _ = std::fs::remove_dir_all(fallback);
+    }
+
+    #[test]
+    fn test_resolve_uses_first_existing_fallback() {
+        let missing = std::env::temp_dir().join("cline-desktop-test-missing-resource");
+        let dev_tree = create_dist_dir("dev-tree");
+
+        let resolved = resolve_cline_core_dir(None, &[missing.clone(), dev_tree.clone()]).unwrap();
+        assert_eq!(resolved, dev_tree);
+
+        assert!(resolve_cline_core_dir(None, &[missing]).is_err());
+        let _ = std::fs::remove_dir_all(dev_tree);
+    }
+
+    #[test]
+    fn test_build_spec_with_node_flags_and_env() {
+        let settings = ClineCoreSettings {
+            dist_dir: None,
+            node_path: Some("/opt/node/bin/node".to_string()),
+            node_args: vec!["--max-old-space-size=4096".to_string(), "--inspect".to_string()],
+            args: vec!["--verbose".to_string()],
+            env: [("NODE_ENV".to_string(), "production".to_string())].into_iter().collect(),
+        };
+
+        let ports = ServicePorts::new(41000, 41001);
+        let spec = build_cline_core_spec(&settings, &PathBuf::from("/opt/cline"), &ports);
+        assert_eq!(spec.program, "/opt/node/bin/node");
+        assert_eq!(spec.args, vec!["--max-old-space-size=4096", "--inspect", "cline-core.js", "--verbose"]);
+        assert_eq!(spec.cwd.as_deref(), Some("/opt/cline"));
+        assert_eq!(spec.env.get("NODE_ENV").map(|s| s.as_str()), Some("production"));
+    }
+
+    #[test]
+    fn test_build_spec_passes_allocated_ports() {
+        let settings = ClineCoreSettings {
+            // 设置中的地址不应覆盖本实例分配的端口
+            env: [("PROTOBUS_ADDRESS".to_string(), "127.0.0.1:26040".to_string())].into_iter().collect(),
+            ..ClineCoreSettings::default()
+        };
+
+        let ports = ServicePorts::new(41000, 41001);
+        let spec = build_cline_core_spec(&settings, &PathBuf::from("/opt/cline"), &ports);
+        assert_eq!(spec.port, Some(41000));
+        assert_eq!(spec.env.get("PROTOBUS_ADDRESS").map(|s| s.as_str()), Some("127.0.0.1:41000"));
+        assert_eq!(spec.env.get("HOST_BRIDGE_ADDRESS").map(|s| s.as_str()), Some("127.0.0.1:41001"));
+        assert_eq!(ports.protobus_endpoint(), "http://127.0.0.1:41000");
+        assert_eq!(ports.hostbridge_endpoint(), "http://127.0.0.1:41001");
+    }
+
+    #[test]
+    fn test_port_taken_output_is_detected() {
+        assert!(is_address_in_use("Error: listen EADDRINUSE: address already in use 127.0.0.1:41000"));
+        assert!(is_address_in_use("No address added out of total 1 resolved: Address already in use"));
+        assert!(!is_address_in_use("ProtoBus server listening on 127.0.0.1:41000"));
+
+        // 换端口时 HostBridge 端口保持不变
+        let ports = ServicePorts::new(41000, 41001).with_protobus(41002);
+        assert_eq!(ports, ServicePorts::new(41002, 41001));
+    }
+
+    #[test]
+    fn test_settings_parse_and_env_overrides() {
+        let mut settings = DesktopSettings::parse(r#"{
+            "clineCore": {
+                "distDir": "/from/settings",
+                "nodeArgs": ["--inspect"]
+            }
+        }"#).unwrap();
+        assert_eq!(settings.cline_core.dist_dir, Some(PathBuf::from("/from/settings")));
+        assert_eq!(settings.cline_core.node_path, None);
+
+        settings.apply_env_overrides(|key| match key {
+            "CLINE_CORE_DIR" => Some("/from/env".to_string()),
+            "CLINE_NODE_ARGS" => Some("--max-old-space-size=2048 --trace-warnings".to_string()),
+            _ => None,
+        });
+        assert_eq!(settings.cline_core.dist_dir, Some(PathBuf::from("/from/env")));
+        assert_eq!(settings.cline_core.node_args, vec!["--max-old-space-size=2048", "--trace-warnings"]);
+    }
+}
+
+#[cfg(test)]
+mod log_file_tests {
+    use crate::process_manager::log_file::{format_line, log_path, parse_line, read_log};
+    use crate::process_manager::{LogQuery, LogRotation, OutputLine, OutputStream, ProcessLog};
+    use std::path::PathBuf;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cline-desktop-logs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn line(timestamp: i64, stream: OutputStream, text: &str) -> OutputLine {
+        OutputLine {
+            timestamp,
+            stream,
+            line: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_and_parse_round_trip() {
+        let original = line(1_700_000_000_123, OutputStream::Stderr, "Error: listen EADDRINUSE");
+        let formatted = format_line(&original);
+        assert_eq!(formatted, "2023-11-14T22:13:20.123Z [stderr] Error: listen EADDRINUSE\n");
+
+        let parsed = parse_line(formatted.trim_end()).unwrap();
+        assert_eq!(parsed.timestamp, original.timestamp);
+        assert_eq!(parsed.stream, OutputStream::Stderr);
+        assert_eq!(parsed.line, original.line);
+
+        assert!(parse_line("not a log line").is_none());
+    }
+
+    #[test]
+    fn test_rotation_keeps_limited_files() {
+        let dir = test_dir("rotation");
+        let rotation = LogRotation { max_bytes: 200, max_files: 2 };
+        let mut log = ProcessLog::open(&dir, "cline-core", rotation.clone()).unwrap();
+
+        for i in 0..40 {
+            log.append(&line(1_700_000_000_000 + i, OutputStream::Stdout, &format!("line {}", i))).unwrap();
+        }
+        log.flush().unwrap();
+
+        let path = log_path(&dir, "cline-core");
+        assert!(path.exists());
+        assert!(dir.join("cline-core.log.1").exists());
+        assert!(dir.join("cline-core.log.2").exists());
+        assert!(!dir.join("cline-core.log.3").exists());
+        assert!(std::fs::metadata(&path).unwrap().len() <= 200);
+
+        // 最旧的记录已被轮转删除，最新的记录按时间顺序保留
+        let entries = read_log(&dir, "cline-core", &rotation, &LogQuery::default()).unwrap();
+        assert!(entries.len() < 40);
+        assert_eq!(entries.last().unwrap().line, "line 39");
+        assert!(entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+        // 只取最后几行时不会读取更旧的文件
+        std::fs::remove_file(dir.join("cline-core.log.2")).unwrap();
+        std::fs::create_dir(dir.join("cline-core.log.2")).unwrap();
+        let tail = read_log(&dir, "cline-core", &rotation, &LogQuery { lines: Some(1), ..LogQuery::default() }).unwrap();
+        assert_eq!(tail.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(), vec!["line 39"]);
+        assert!(read_log(&dir, "cline-core", &rotation, &LogQuery::default()).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_read_log_by_lines_and_time_range() {
+        let dir = test_dir("query");
+        let rotation = LogRotation::default();
+        let mut log = ProcessLog::open(&dir, "node-server-3000", rotation.clone()).unwrap();
+        log.append(&line(1000, OutputStream::System, "Process started (pid 42): node-server 3000")).unwrap();
+        log.append(&line(2000, OutputStream::Stdout, "first\nsecond")).unwrap();
+        log.append(&line(3000, OutputStream::Stderr, "warning")).unwrap();
+        log.append(&line(4000, OutputStream::Stdout, "done")).unwrap();
+        log.flush().unwrap();
+
+        let last_two = read_log(&dir, "node-server-3000", &rotation, &LogQuery { lines: Some(2), ..LogQuery::default() }).unwrap();
+        assert_eq!(last_two.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(), vec!["warning", "done"]);
+
+        let range = LogQuery { lines: None, since: Some(2000), until: Some(3000) };
+        let entries = read_log(&dir, "node-server-3000", &rotation, &range).unwrap();
+        assert_eq!(entries.len(), 2);
+        // 包含换行的输出作为一条记录读回
+        assert_eq!(entries[0].line, "first\nsecond");
+        assert_eq!(entries[1].stream, OutputStream::Stderr);
+
+        // 不存在的进程没有日志
+        assert!(read_log(&dir, "missing", &rotation, &LogQuery::default()).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_log_path_sanitizes_id() {
+        let dir = PathBuf::from("/var/log/cline");
+        assert_eq!(log_path(&dir, "cline-core"), dir.join("cline-core.log"));
+        assert_eq!(log_path(&dir, "../etc/passwd"), dir.join(".._etc_passwd.log"));
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use crate::process_manager::monitor::MonitorConfig;
+    use crate::process_manager::stats::{cpu_percent, parse_stat, process_tree, tree_usage, ProcStat};
+    use crate::settings::MonitoringSettings;
+    use std::time::Duration;
+
+    fn stat(pid: u32, ppid: u32) -> ProcStat {
+        ProcStat { pid, ppid, state: 'S', start_time: 100, cpu_ticks: 10, rss_pages: 1 }
+    }
+
+    #[test]
+    fn test_parse_stat_with_spaces_in_name() {
+        let content = "4242 (node (cline core)) S 4200 4242 4242 0 -1 4194560 1000 0 0 0 150 50 0 0 20 0 11 0 12345 1073741824 25600 18446744073709551615 1 1 0 0 0 0 0 16781312 134235650 0 0 0 17 3 0 0 0 0 0";
+        let parsed = parse_stat(content).unwrap();
+        assert_eq!(parsed.pid, 4242);
+        assert_eq!(parsed.ppid, 4200);
+        assert_eq!(parsed.state, 'S');
+        assert_eq!(parsed.start_time, 12345);
+        assert_eq!(parsed.cpu_ticks, 200);
+        assert_eq!(parsed.rss_pages, 25600);
+
+        assert!(parse_stat("garbage").is_none());
+    }
+
+    #[test]
+    fn test_process_tree_includes_descendants() {
+        let processes = vec![stat(1, 0), stat(10, 1), stat(11, 10), stat(12, 11), stat(20, 1), stat(13, 10)];
+        let mut tree = process_tree(10, &processes);
+        tree.sort();
+        assert_eq!(tree, vec![10, 11, 12, 13]);
+
+        let usage = tree_usage(10, &processes).unwrap();
+        assert_eq!(usage.process_count, 4);
+        assert_eq!(usage.cpu_ticks, 40);
+        assert!(tree_usage(99, &processes).is_none());
+    }
+
+    #[test]
+    fn test_cpu_percent() {
+        // 1 秒内消耗 50 个节拍（100 节拍/秒）即 50%
+        assert_eq!(cpu_percent(100, 150, Duration::from_secs(1), 100), 50.0);
+        assert_eq!(cpu_percent(100, 300, Duration::from_secs(1), 100), 200.0);
+        assert_eq!(cpu_percent(100, 150, Duration::ZERO, 100), 0.0);
+        // 节拍数回退（pid 被复用）时不产生负值
+        assert_eq!(cpu_percent(150, 100, Duration::from_secs(1), 100), 0.0);
+    }
+
+    #[test]
+    fn test_memory_limit_per_process_overrides_global() {
+        let settings = MonitoringSettings {
+            interval_ms: 1000,
+            memory_limit_mb: Some(2048),
+            memory_limits_mb: [("cline-core".to_string(), 4096)].into_iter().collect(),
+        };
+        let config = MonitorConfig::from_settings(&settings);
+        assert_eq!(config.memory_limit_for("cline-core"), Some(4096 * 1024 * 1024));
+        assert_eq!(config.memory_limit_for("node-server-3000"), Some(2048 * 1024 * 1024));
+        assert_eq!(MonitorConfig::from_settings(&MonitoringSettings::default()).memory_limit_for("cline-core"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sample_current_process() {
+        let all_stats = crate::process_manager::stats::read_all_stats();
+        let usage = tree_usage(std::process::id(), &all_stats).unwrap();
+        assert!(usage.rss_bytes > 0);
+        assert!(usage.open_fds > 0);
+        assert!(usage.process_count >= 1);
+    }
+}
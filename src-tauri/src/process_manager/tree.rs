@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use super::stats;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeSignal {
+    // SIGTERM，请求进程自行退出
+    Terminate,
+    // SIGKILL，强制结束
+    Kill,
+}
+
+// 进程树中的一个进程；同时记录启动时间，避免误杀复用了 pid 的无关进程
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeMember {
+    pub pid: u32,
+    pub start_time: u64,
+}
+
+// 子进程及其所有子孙进程的快照
+#[derive(Debug, Clone)]
+pub struct ProcessTree {
+    pub root: u32,
+    // 子进程自己是进程组组长时的进程组 id
+    pub pgid: Option<u32>,
+    pub members: Vec<TreeMember>,
+}
+
+impl ProcessTree {
+    // 必须在发送信号之前记录：父进程退出后，子孙进程会被 init 收养，无法再通过父子关系找到
+    pub fn snapshot(root: u32) -> Self {
+        let all_stats = stats::read_all_stats();
+        let by_pid: HashMap<u32, u64> = all_stats.iter().map(|s| (s.pid, s.start_time)).collect();
+        let mut members: Vec<TreeMember> = stats::process_tree(root, &all_stats)
+            .into_iter()
+            .filter_map(|pid| by_pid.get(&pid).map(|start_time| TreeMember { pid, start_time: *start_time }))
+            .collect();
+        // 没有 /proc 的平台上只能跟踪直接子进程
+        if members.is_empty() && !cfg!(target_os = "linux") {
+            members.push(TreeMember { pid: root, start_time: 0 });
+        }
+
+        Self {
+            root,
+            pgid: own_process_group(root),
+            members,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    // 向进程组以及树中仍然存活的每个进程发送信号
+    // 终端、MCP 服务器等可能创建了自己的会话，不在子进程的进程组中，因此需要逐个发送
+    pub fn signal(&self, signal: TreeSignal) {
+        if let Some(pgid) = self.pgid {
+            send_signal(-(pgid as i64), signal);
+        }
+        for member in self.members.iter().filter(|m| is_alive(m)) {
+            send_signal(member.pid as i64, signal);
+        }
+    }
+
+    // 仍然存活的进程
+    pub fn survivors(&self) -> Vec<u32> {
+        self.members
+            .iter()
+            .filter(|m| is_alive(m))
+            .map(|m| m.pid)
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn own_process_group(pid: u32) -> Option<u32> {
+    let pgid = unsafe { libc::getpgid(pid as libc::pid_t) };
+    // 只有子进程是自己进程组的组长时才按组发送，避免把信号发给本应用所在的进程组
+    if pgid > 0 && pgid as u32 == pid && pgid != unsafe { libc::getpgrp() } {
+        Some(pgid as u32)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn own_process_group(_pid: u32) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn send_signal(target: i64, signal: TreeSignal) {
+    let signal = match signal {
+        TreeSignal::Terminate => libc::SIGTERM,
+        TreeSignal::Kill => libc::SIGKILL,
+    };
+    unsafe {
+        libc::kill(target as libc::pid_t, signal);
+    }
+}
+
+// 非 Unix 平台只能通过 CommandChild::kill 结束直接子进程
+#[cfg(not(unix))]
+fn send_signal(_target: i64, _signal: TreeSignal) {}
+
+// 僵尸进程已经退出，只是尚未被回收，视为已结束
+#[cfg(target_os = "linux")]
+fn is_alive(member: &TreeMember) -> bool {
+    match stats::read_stat(member.pid) {
+        Some(stat) => stat.start_time == member.start_time && stat.state != 'Z',
+        None => false,
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_alive(member: &TreeMember) -> bool {
+    unsafe { libc::kill(member.pid as libc::pid_t, 0) == 0 }
+}
+
+// 无法检查进程状态；非 Unix 平台上 terminate_gracefully 总会直接结束子进程，不依赖这里的结果
+#[cfg(not(unix))]
+fn is_alive(_member: &TreeMember) -> bool {
+    false
+}
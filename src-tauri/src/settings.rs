@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+// 桌面端设置文件名，位于应用配置目录下
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+// 指定设置文件路径的环境变量
+pub const SETTINGS_PATH_ENV: &str = "CLINE_DESKTOP_SETTINGS";
+
+// cline-core 相关的环境变量覆盖
+pub const CLINE_CORE_DIR_ENV: &str = "CLINE_CORE_DIR";
+pub const CLINE_NODE_PATH_ENV: &str = "CLINE_NODE_PATH";
+pub const CLINE_NODE_ARGS_ENV: &str = "CLINE_NODE_ARGS";
+// 子进程内存上限（MB）的环境变量覆盖
+pub const MEMORY_LIMIT_ENV: &str = "CLINE_DESKTOP_MEMORY_LIMIT_MB";
+// 设置为 1 或 true 时进入离线演示模式
+pub const OFFLINE_MODE_ENV: &str = "CLINE_DESKTOP_OFFLINE";
+
+// Cline Desktop 的本地设置
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DesktopSettings {
+    pub cline_core: ClineCoreSettings,
+    pub monitoring: MonitoringSettings,
+    pub offline: OfflineSettings,
+    pub local_models: LocalModelsSettings,
+}
+
+// cline-core 进程的启动设置
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClineCoreSettings {
+    // 包含 cline-core.js 的目录，未设置时自动查找
+    pub dist_dir: Option<PathBuf>,
+    // Node 可执行文件，默认使用 PATH 中的 node
+    pub node_path: Option<String>,
+    // 传给 Node 的额外参数，例如 --max-old-space-size=4096、--inspect
+    pub node_args: Vec<String>,
+    // 传给 cline-core.js 的额外参数
+    pub args: Vec<String>,
+    // 额外的环境变量
+    pub env: HashMap<String, String>,
+}
+
+// 子进程资源监控设置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MonitoringSettings {
+    // 采样间隔，为 0 时关闭监控
+    pub interval_ms: u64,
+    // 所有子进程（含子孙进程）的内存上限，超过后重启该进程
+    pub memory_limit_mb: Option<u64>,
+    // 按进程 id 单独设置的内存上限，优先于 memory_limit_mb
+    pub memory_limits_mb: HashMap<String, u64>,
+}
+
+impl Default for MonitoringSettings {
+    fn default() -> Self {
+        Self {
+            interval_ms: 5000,
+            memory_limit_mb: None,
+            memory_limits_mb: HashMap::new(),
+        }
+    }
+}
+
+// 离线演示模式：不启动 cline-core，cline.* 请求使用预置数据响应
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OfflineSettings {
+    pub enabled: bool,
+    // 额外的数据目录，其中的 *.json 会覆盖内置数据
+    pub fixtures_dir: Option<PathBuf>,
+}
+
+// 本地模型发现：直接查询本机的 Ollama 和 LM Studio
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalModelsSettings {
+    // 请求中没有携带地址时使用，未设置时为各自的默认端口
+    pub ollama_base_url: Option<String>,
+    pub lm_studio_base_url: Option<String>,
+    pub timeout_ms: u64,
+}
+
+impl Default for LocalModelsSettings {
+    fn default() -> Self {
+        Self {
+            ollama_base_url: None,
+            lm_studio_base_url: None,
+            timeout_ms: 1500,
+        }
+    }
+}
+
+impl DesktopSettings {
+    // 读取设置文件并应用环境变量覆盖；文件不存在或无法解析时使用默认值
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let mut settings = match settings_path(app_handle) {
+            Some(path) if path.exists() => match std::fs::read_to_string(&path) {
+                Ok(content) => Self::parse(&content).unwrap_or_else(|e| {
+                    eprintln!("[SETTINGS] Failed to parse {}: {}, using defaults", path.display(), e);
+                    Self::default()
+                }),
+                Err(e) => {
+                    eprintln!("[SETTINGS] Failed to read {}: {}, using defaults", path.display(), e);
+                    Self::default()
+                }
+            },
+            _ => Self::default(),
+        };
+
+        settings.apply_env_overrides(|key| std::env::var(key).ok());
+        settings
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| e.to_string())
+    }
+
+    // 环境变量优先于设置文件
+    pub fn apply_env_overrides<F: Fn(&str) -> Option<String>>(&mut self, get_env: F) {
+        if let Some(dir) = get_env(CLINE_CORE_DIR_ENV).filter(|v| !v.is_empty()) {
+            self.cline_core.dist_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(node_path) = get_env(CLINE_NODE_PATH_ENV).filter(|v| !v.is_empty()) {
+            self.cline_core.node_path = Some(node_path);
+        }
+        if let Some(node_args) = get_env(CLINE_NODE_ARGS_ENV) {
+            self.cline_core.node_args = node_args.split_whitespace().map(|s| s.to_string()).collect();
+        }
+        if let Some(limit) = get_env(MEMORY_LIMIT_ENV).and_then(|v| v.trim().parse().ok()) {
+            self.monitoring.memory_limit_mb = Some(limit);
+        }
+        if let Some(offline) = get_env(OFFLINE_MODE_ENV) {
+            self.offline.enabled = matches!(offline.trim().to_lowercase().as_str(), "1" | "true" | "yes");
+        }
+    }
+}
+
+// 设置文件路径：优先使用环境变量，其次为应用配置目录
+pub fn settings_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(SETTINGS_PATH_ENV) {
+        if !path.is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    app_handle
+        .path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join(SETTINGS_FILE_NAME))
+}
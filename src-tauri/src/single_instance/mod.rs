@@ -0,0 +1,309 @@
+pub mod workspace;
+
+#[cfg(test)]
+mod tests;
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+// 锁文件名，内容为持有锁的实例的 pid、IPC 端口和令牌
+pub const LOCK_FILE_NAME: &str = "cline-desktop.lock";
+// 指定锁文件目录的环境变量
+pub const INSTANCE_DIR_ENV: &str = "CLINE_DESKTOP_INSTANCE_DIR";
+// 设置为 1 时允许同时运行多个实例
+pub const MULTI_INSTANCE_ENV: &str = "CLINE_DESKTOP_MULTI_INSTANCE";
+
+// 与已运行实例通信的超时时间
+const HAND_OFF_TIMEOUT: Duration = Duration::from_secs(3);
+// 锁文件可能刚创建、尚未写入内容，读取失败时的重试次数
+const READ_LOCK_ATTEMPTS: usize = 5;
+// 已运行的实例可能正忙于启动而暂时无法应答，转交失败时的重试次数和间隔
+const FORWARD_ATTEMPTS: usize = 3;
+const FORWARD_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    // 进程启动时间，用于识别被其它进程复用的 pid；只在 Linux 上记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_time: Option<u64>,
+    port: u16,
+    token: String,
+}
+
+// 第二个实例转交给已运行实例的内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandOff {
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HandOffMessage {
+    token: String,
+    #[serde(flatten)]
+    hand_off: HandOff,
+}
+
+pub enum Acquired {
+    // 本进程是唯一实例
+    Primary(InstanceLock),
+    // 已有实例在运行，启动参数已转交给它
+    Secondary,
+}
+
+// 本实例持有的锁，以及接收其它实例转交请求的本地 socket
+pub struct InstanceLock {
+    path: PathBuf,
+    listener: Option<TcpListener>,
+    token: String,
+}
+
+impl InstanceLock {
+    // 在后台线程中接收其它实例转交的工作区
+    pub fn listen<F>(&mut self, on_hand_off: F) -> std::io::Result<()>
+    where
+        F: Fn(HandOff) + Send + 'static,
+    {
+        let listener = self.listener.take().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::Other, "Single-instance listener already started")
+        })?;
+        let token = self.token.clone();
+
+        std::thread::Builder::new()
+            .name("single-instance".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if let Some(hand_off) = read_hand_off(stream, &token) {
+                                on_hand_off(hand_off);
+                            }
+                        }
+                        Err(e) => eprintln!("[INSTANCE] Failed to accept connection: {}", e),
+                    }
+                }
+            })?;
+        Ok(())
+    }
+}
+
+impl Drop for InstanceLock {
+    // 仅删除自己创建的锁文件；异常退出时残留的锁会在下次启动时被识别并清除
+    fn drop(&mut self) {
+        if read_lock_once(&self.path).map(|info| info.pid) == Some(std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+pub fn enabled() -> bool {
+    !matches!(std::env::var(MULTI_INSTANCE_ENV).as_deref(), Ok("1") | Ok("true"))
+}
+
+// 锁文件所在目录，按用户区分
+pub fn instance_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var(INSTANCE_DIR_ENV) {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    let base = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "default".to_string());
+    base.join(format!("cline-desktop-{}", user))
+}
+
+// 获取单实例锁；若已有实例在运行，则把 hand_off 转交给它
+// 持有锁的实例仍在运行却无法完成转交时返回 AlreadyExists 错误，调用方不应再作为主实例启动
+pub fn acquire_or_forward(dir: &Path, hand_off: &HandOff) -> std::io::Result<Acquired> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(LOCK_FILE_NAME);
+
+    // 第二次尝试发生在清除残留的锁文件之后
+    for _ in 0..2 {
+        match create_lock_file(&path) {
+            Ok(mut file) => {
+                let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+                let info = LockInfo {
+                    pid: std::process::id(),
+                    start_time: crate::process_manager::stats::read_stat(std::process::id()).map(|stat| stat.start_time),
+                    port: listener.local_addr()?.port(),
+                    token: new_token(),
+                };
+                file.write_all(serde_json::to_string(&info)?.as_bytes())?;
+                println!("[INSTANCE] Acquired single-instance lock {} (IPC port {})", path.display(), info.port);
+
+                return Ok(Acquired::Primary(InstanceLock {
+                    path,
+                    listener: Some(listener),
+                    token: info.token,
+                }));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                if let Some(info) = read_lock(&path) {
+                    if forward_while_running(&info, hand_off)? {
+                        return Ok(Acquired::Secondary);
+                    }
+                }
+
+                // 持有锁的实例已不存在
+                println!("[INSTANCE] Removing stale lock file {}", path.display());
+                let _ = fs::remove_file(&path);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!("Could not acquire single-instance lock {}", path.display()),
+    ))
+}
+
+fn create_lock_file(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // 锁文件中包含令牌，只允许当前用户读取
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    for attempt in 0..READ_LOCK_ATTEMPTS {
+        if let Some(info) = read_lock_once(path) {
+            return Some(info);
+        }
+        if attempt + 1 < READ_LOCK_ATTEMPTS {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    None
+}
+
+fn read_lock_once(path: &Path) -> Option<LockInfo> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn forward(info: &LockInfo, hand_off: &HandOff) -> std::io::Result<()> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, info.port));
+    let mut stream = TcpStream::connect_timeout(&addr, HAND_OFF_TIMEOUT)?;
+    stream.set_read_timeout(Some(HAND_OFF_TIMEOUT))?;
+
+    let message = HandOffMessage {
+        token: info.token.clone(),
+        hand_off: hand_off.clone(),
+    };
+    writeln!(stream, "{}", serde_json::to_string(&message)?)?;
+
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    match reply.trim() {
+        "ok" => {
+            println!("[INSTANCE] Handed off to running instance (pid {})", info.pid);
+            Ok(())
+        }
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Unexpected reply from running instance: {}", other),
+        )),
+    }
+}
+
+// 转交给持有锁的实例，返回 false 表示该实例已经退出、锁文件可以清除
+fn forward_while_running(info: &LockInfo, hand_off: &HandOff) -> std::io::Result<bool> {
+    for attempt in 1..=FORWARD_ATTEMPTS {
+        let error = match forward(info, hand_off) {
+            Ok(()) => return Ok(true),
+            Err(e) => e,
+        };
+        if !holder_is_running(info, &error) {
+            println!("[INSTANCE] Instance {} is no longer running: {}", info.pid, error);
+            return Ok(false);
+        }
+        if attempt == FORWARD_ATTEMPTS {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Instance {} is running but did not accept the hand-off: {}", info.pid, error),
+            ));
+        }
+        println!("[INSTANCE] Instance {} is not responding, retrying: {}", info.pid, error);
+        std::thread::sleep(FORWARD_RETRY_DELAY);
+    }
+    Ok(false)
+}
+
+// 无法检查进程的平台上，只有 IPC 端口拒绝连接时才认为实例已经退出
+fn holder_is_running(info: &LockInfo, forward_error: &std::io::Error) -> bool {
+    process_is_running(info).unwrap_or(forward_error.kind() != std::io::ErrorKind::ConnectionRefused)
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_running(info: &LockInfo) -> Option<bool> {
+    let running = crate::process_manager::stats::read_stat(info.pid).map_or(false, |stat| {
+        stat.state != 'Z' && info.start_time.map_or(true, |start_time| start_time == stat.start_time)
+    });
+    Some(running)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_is_running(info: &LockInfo) -> Option<bool> {
+    let result = unsafe { libc::kill(info.pid as libc::pid_t, 0) };
+    // EPERM 表示进程存在但属于其他用户
+    Some(result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+}
+
+#[cfg(not(unix))]
+fn process_is_running(_info: &LockInfo) -> Option<bool> {
+    None
+}
+
+fn read_hand_off(stream: TcpStream, token: &str) -> Option<HandOff> {
+    stream.set_read_timeout(Some(HAND_OFF_TIMEOUT)).ok()?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+    let message: HandOffMessage = match serde_json::from_str(&line) {
+        Ok(message) => message,
+        Err(e) => {
+            eprintln!("[INSTANCE] Ignoring malformed hand-off message: {}", e);
+            return None;
+        }
+    };
+
+    // 本地端口对所有用户可见，只接受持有令牌的请求
+    if message.token != token {
+        eprintln!("[INSTANCE] Rejecting hand-off with invalid token");
+        let _ = writeln!(&stream, "denied");
+        return None;
+    }
+
+    let _ = writeln!(&stream, "ok");
+    Some(message.hand_off)
+}
+
+fn new_token() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u128(nanos);
+    format!("{:016x}", hasher.finish())
+}
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod single_instance_tests {
+    use crate::single_instance::{acquire_or_forward, workspace::workspace_from_args, Acquired, HandOff, LOCK_FILE_NAME};
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cline-desktop-instance-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_second_instance_forwards_workspace() {
+        let dir = test_dir("forward");
+        let mut lock = match acquire_or_forward(&dir, &HandOff { workspace: None }).unwrap() {
+            Acquired::Primary(lock) => lock,
+            Acquired::Secondary => panic!("first instance should acquire the lock"),
+        };
+
+        let (tx, rx) = mpsc::channel();
+        lock.listen(move |hand_off| {
+            let _ = tx.send(hand_off);
+        })
+        .unwrap();
+
+        let hand_off = HandOff { workspace: Some("/home/user/project".to_string()) };
+        assert!(matches!(acquire_or_forward(&dir, &hand_off).unwrap(), Acquired::Secondary));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), hand_off);
+
+        drop(lock);
+        assert!(!dir.join(LOCK_FILE_NAME).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn unused_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    // 已经退出并被回收的进程的 pid
+    fn exited_pid() -> u32 {
+        let mut child = std::process::Command::new(if cfg!(windows) { "cmd" } else { "true" })
+            .args(if cfg!(windows) { &["/C", "exit"][..] } else { &[][..] })
+            .spawn()
+            .unwrap();
+        child.wait().unwrap();
+        child.id()
+    }
+
+    #[test]
+    fn test_stale_lock_is_replaced() {
+        let dir = test_dir("stale");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // 进程已经退出且端口上没有监听者，模拟异常退出后残留的锁文件
+        std::fs::write(
+            dir.join(LOCK_FILE_NAME),
+            format!(r#"{{"pid": {}, "port": {}, "token": "stale"}}"#, exited_pid(), unused_port()),
+        )
+        .unwrap();
+
+        let acquired = acquire_or_forward(&dir, &HandOff { workspace: None }).unwrap();
+        assert!(matches!(acquired, Acquired::Primary(_)));
+
+        drop(acquired);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_hand_off_with_wrong_token_is_rejected() {
+        let dir = test_dir("token");
+        let mut lock = match acquire_or_forward(&dir, &HandOff { workspace: None }).unwrap() {
+            Acquired::Primary(lock) => lock,
+            Acquired::Secondary => panic!("first instance should acquire the lock"),
+        };
+        let (tx, rx) = mpsc::channel();
+        lock.listen(move |hand_off| {
+            let _ = tx.send(hand_off);
+        })
+        .unwrap();
+
+        // 伪造令牌后再次启动：转交失败，但持有锁的进程仍在运行，不能成为第二个主实例
+        let content = std::fs::read_to_string(dir.join(LOCK_FILE_NAME)).unwrap();
+        let mut info: serde_json::Value = serde_json::from_str(&content).unwrap();
+        info["token"] = serde_json::json!("forged");
+        std::fs::write(dir.join(LOCK_FILE_NAME), info.to_string()).unwrap();
+
+        let error = acquire_or_forward(&dir, &HandOff { workspace: Some("/tmp".to_string()) })
+            .err()
+            .expect("second instance must not acquire the lock");
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        assert_eq!(std::fs::read_to_string(dir.join(LOCK_FILE_NAME)).unwrap(), info.to_string());
+
+        drop(lock);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_lock_with_reused_pid_is_stale() {
+        let dir = test_dir("reused");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // pid 仍在运行，但启动时间不同，说明 pid 已被其它进程复用
+        std::fs::write(
+            dir.join(LOCK_FILE_NAME),
+            format!(r#"{{"pid": {}, "start_time": 1, "port": {}, "token": "stale"}}"#, std::process::id(), unused_port()),
+        )
+        .unwrap();
+
+        let acquired = acquire_or_forward(&dir, &HandOff { workspace: None }).unwrap();
+        assert!(matches!(acquired, Acquired::Primary(_)));
+
+        drop(acquired);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_workspace_from_args() {
+        let cwd = test_dir("args");
+        std::fs::create_dir_all(cwd.join("project")).unwrap();
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let workspace = workspace_from_args(args(&["cline-desktop", "--verbose", "project"]), &cwd).unwrap();
+        assert_eq!(PathBuf::from(workspace), cwd.join("project").canonicalize().unwrap());
+
+        assert_eq!(workspace_from_args(args(&["cline-desktop"]), &cwd), None);
+        assert_eq!(workspace_from_args(args(&["cline-desktop", "missing"]), &cwd), None);
+
+        let _ = std::fs::remove_dir_all(cwd);
+    }
+}
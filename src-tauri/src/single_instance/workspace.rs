@@ -0,0 +1,101 @@
+use std::path::Path;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::HandOff;
+use crate::hostbridge::WorkspaceState;
+use crate::process_manager::{self, SharedProcessManager, CLINE_CORE_ID};
+
+// 另一个工作区请求打开时发送给前端的事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceOpenRequested {
+    pub path: String,
+    pub current: Option<String>,
+}
+
+// 从命令行参数中取出工作区目录：第一个不以 - 开头且存在的目录
+pub fn workspace_from_args<I>(args: I, cwd: &Path) -> Option<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    args.into_iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .map(|arg| cwd.join(arg))
+        .find(|path| path.is_dir())
+        .map(|path| {
+            path.canonicalize()
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        })
+}
+
+// 打开工作区；若 cline-core 已在运行，则重启它以切换到新的工作区
+// 返回工作区是否发生了变化。会等待旧的 cline-core 退出，不应在异步运行时的工作线程上调用
+pub fn open_workspace(
+    app_handle: &AppHandle,
+    process_manager: &SharedProcessManager,
+    path: String,
+) -> Result<bool, String> {
+    let state = app_handle
+        .try_state::<WorkspaceState>()
+        .ok_or_else(|| "Workspace state is not available".to_string())?;
+
+    if !state.set(path.clone()) {
+        println!("[WORKSPACE] Workspace {} is already open", path);
+        return Ok(false);
+    }
+    println!("[WORKSPACE] Opened workspace {}", path);
+
+    // cline-core 尚未启动时，启动后会通过 HostBridge 读取到新的工作区
+    let registered = process_manager.lock().unwrap().spec(CLINE_CORE_ID).is_some();
+    if registered {
+        // 让 cline-core 保存任务状态，并等待它释放 ProtoBus 端口后再启动新进程
+        process_manager::shutdown::stop_gracefully(process_manager, CLINE_CORE_ID, true)?;
+        let pid = process_manager::cline_core::start_supervised(app_handle, process_manager)?;
+        println!("[WORKSPACE] cline-core restarted for new workspace (pid {})", pid);
+    }
+
+    process_manager::cline_core::emit_to_main(app_handle, "workspace-changed", path);
+    Ok(true)
+}
+
+// 处理第二个实例转交的启动请求
+pub fn handle_hand_off(app_handle: &AppHandle, hand_off: HandOff) {
+    println!("[INSTANCE] Received hand-off from another instance: {:?}", hand_off);
+    focus_main_window(app_handle);
+
+    let path = match hand_off.workspace {
+        Some(path) => path,
+        None => return,
+    };
+    let current = app_handle
+        .try_state::<WorkspaceState>()
+        .and_then(|state| state.get());
+
+    match current.as_deref() {
+        // 尚未打开工作区，直接在现有窗口中打开
+        None => {
+            let process_manager = app_handle.state::<SharedProcessManager>();
+            if let Err(e) = open_workspace(app_handle, process_manager.inner(), path) {
+                eprintln!("[INSTANCE] Failed to open forwarded workspace: {}", e);
+            }
+        }
+        Some(current_path) if current_path == path => {}
+        // 切换工作区会中断当前任务，交由前端确认后调用 open_workspace
+        Some(_) => {
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.emit("workspace-open-requested", WorkspaceOpenRequested { path, current: current.clone() });
+            }
+        }
+    }
+}
+
+pub fn focus_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}